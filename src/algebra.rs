@@ -0,0 +1,271 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::iter::{FusedIterator, Peekable};
+
+use crate::{Arena, NodeId, Storage, Xfast, XfastIter, XfastKey};
+
+/// Lazy iterator around the keys present in either of two tries, in ascending order.
+///
+/// This is constructed from the `union` method on `Xfast` and `XfastSet`.
+pub struct Union<'a, K: XfastKey, T, U, A: Storage<K, T> = Arena<K, T>, B: Storage<K, U> = Arena<K, U>> {
+    left: Peekable<XfastIter<'a, K, T, A>>,
+    right: Peekable<XfastIter<'a, K, U, B>>,
+}
+
+impl<'a, K: XfastKey, T, U, A: Storage<K, T>, B: Storage<K, U>> Union<'a, K, T, U, A, B> {
+    pub(crate) fn new<S: BuildHasher>(left: &'a Xfast<K, T, S, A>, right: &'a Xfast<K, U, S, B>) -> Self {
+        Union {
+            left: left.iter().peekable(),
+            right: right.iter().peekable(),
+        }
+    }
+}
+
+impl<'a, K: XfastKey, T, U, A: Storage<K, T>, B: Storage<K, U>> Iterator for Union<'a, K, T, U, A, B> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let order = match (self.left.peek(), self.right.peek()) {
+            (Some((left_key, _)), Some((right_key, _))) => left_key.cmp(right_key),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        match order {
+            Ordering::Less => self.left.next().map(|(&key, _)| key),
+            Ordering::Greater => self.right.next().map(|(&key, _)| key),
+            Ordering::Equal => {
+                self.right.next();
+                self.left.next().map(|(&key, _)| key)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left, _) = self.left.size_hint();
+        let (right, _) = self.right.size_hint();
+        (left.max(right), Some(left + right))
+    }
+}
+
+impl<'a, K: XfastKey, T, U, A: Storage<K, T>, B: Storage<K, U>> FusedIterator for Union<'a, K, T, U, A, B> {}
+
+/// Lazy iterator around the keys present in both of two tries, in ascending order.
+///
+/// Instead of walking both tries, the iterator leapfrogs between them: the successor of a key in one trie is looked up
+/// in the other, so long runs of keys missing from the other trie are skipped in a single successor query.
+///
+/// This is constructed from the `intersection` method on `Xfast` and `XfastSet`.
+pub struct Intersection<'a, K, T, U, S = RandomState, A = Arena<K, T>, B = Arena<K, U>> {
+    left: &'a Xfast<K, T, S, A>,
+    right: &'a Xfast<K, U, S, B>,
+    // next leaf of the left trie which may be in the right trie
+    next_left: Option<NodeId>,
+}
+
+impl<'a, K: XfastKey, T, U, S: BuildHasher, A: Storage<K, T>, B: Storage<K, U>> Intersection<'a, K, T, U, S, A, B> {
+    pub(crate) fn new(left: &'a Xfast<K, T, S, A>, right: &'a Xfast<K, U, S, B>) -> Self {
+        Intersection {
+            left,
+            right,
+            next_left: left.first_leaf(),
+        }
+    }
+}
+
+impl<'a, K: XfastKey, T, U, S: BuildHasher, A: Storage<K, T>, B: Storage<K, U>> Iterator
+    for Intersection<'a, K, T, U, S, A, B> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        loop {
+            let left_node = self.next_left?;
+            let key = self.left.arena[left_node].key;
+            // skip to the smallest key of the right trie which is not smaller than the key of the left trie
+            let right_key = match self.right.find_successor_id(key) {
+                Some(right_node) => self.right.arena[right_node].key,
+                None => {
+                    self.next_left = None;
+                    return None;
+                }
+            };
+            if right_key == key {
+                self.next_left = self.left.arena[left_node].right;
+                return Some(key);
+            }
+            // and back to the smallest key of the left trie which is not smaller than the key of the right trie
+            self.next_left = self.left.find_successor_id(right_key);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.left.len().min(self.right.len())))
+    }
+}
+
+impl<'a, K: XfastKey, T, U, S: BuildHasher, A: Storage<K, T>, B: Storage<K, U>> FusedIterator
+    for Intersection<'a, K, T, U, S, A, B> {}
+
+/// Lazy iterator around the keys present in the first trie but not in the second, in ascending order.
+///
+/// This is constructed from the `difference` method on `Xfast` and `XfastSet`.
+pub struct Difference<'a, K, T, U, S = RandomState, A = Arena<K, T>, B = Arena<K, U>> {
+    left: XfastIter<'a, K, T, A>,
+    right: &'a Xfast<K, U, S, B>,
+}
+
+impl<'a, K: XfastKey, T, U, S: BuildHasher, A: Storage<K, T>, B: Storage<K, U>> Difference<'a, K, T, U, S, A, B> {
+    pub(crate) fn new(left: &'a Xfast<K, T, S, A>, right: &'a Xfast<K, U, S, B>) -> Self {
+        Difference {
+            left: left.iter(),
+            right,
+        }
+    }
+}
+
+impl<'a, K: XfastKey, T, U, S: BuildHasher, A: Storage<K, T>, B: Storage<K, U>> Iterator
+    for Difference<'a, K, T, U, S, A, B> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let right = self.right;
+        // membership is a single lookup in the leaf level of the right trie
+        self.left.by_ref().map(|(&key, _)| key).find(|key| !right.contains_key(key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.left.len()))
+    }
+}
+
+impl<'a, K: XfastKey, T, U, S: BuildHasher, A: Storage<K, T>, B: Storage<K, U>> FusedIterator
+    for Difference<'a, K, T, U, S, A, B> {}
+
+/// Lazy iterator around the keys present in exactly one of two tries, in ascending order.
+///
+/// This is constructed from the `symmetric_difference` method on `Xfast` and `XfastSet`.
+pub struct SymmetricDifference<'a, K: XfastKey, T, U, A: Storage<K, T> = Arena<K, T>, B: Storage<K, U> = Arena<K, U>> {
+    left: Peekable<XfastIter<'a, K, T, A>>,
+    right: Peekable<XfastIter<'a, K, U, B>>,
+}
+
+impl<'a, K: XfastKey, T, U, A: Storage<K, T>, B: Storage<K, U>> SymmetricDifference<'a, K, T, U, A, B> {
+    pub(crate) fn new<S: BuildHasher>(left: &'a Xfast<K, T, S, A>, right: &'a Xfast<K, U, S, B>) -> Self {
+        SymmetricDifference {
+            left: left.iter().peekable(),
+            right: right.iter().peekable(),
+        }
+    }
+}
+
+impl<'a, K: XfastKey, T, U, A: Storage<K, T>, B: Storage<K, U>> Iterator for SymmetricDifference<'a, K, T, U, A, B> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        loop {
+            let order = match (self.left.peek(), self.right.peek()) {
+                (Some((left_key, _)), Some((right_key, _))) => left_key.cmp(right_key),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => return None,
+            };
+            match order {
+                Ordering::Less => return self.left.next().map(|(&key, _)| key),
+                Ordering::Greater => return self.right.next().map(|(&key, _)| key),
+                Ordering::Equal => {
+                    self.left.next();
+                    self.right.next();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left, _) = self.left.size_hint();
+        let (right, _) = self.right.size_hint();
+        (0, Some(left + right))
+    }
+}
+
+impl<'a, K: XfastKey, T, U, A: Storage<K, T>, B: Storage<K, U>> FusedIterator for SymmetricDifference<'a, K, T, U, A, B> {}
+
+#[cfg(test)]
+mod test {
+    use crate::{Xfast, XfastSet};
+
+    fn init() -> (XfastSet<u32>, XfastSet<u32>) {
+        let mut odd: XfastSet<u32> = XfastSet::new(1023);
+        let mut squares: XfastSet<u32> = XfastSet::new(1023);
+        for key in 0..32 {
+            odd.insert(2 * key + 1);
+            squares.insert(key * key);
+        }
+        (odd, squares)
+    }
+
+    #[test]
+    fn union() -> Result<(), String> {
+        let (odd, squares) = init();
+        let expected: Vec<u32> = (0..1024).filter(|key| key % 2 == 1 && *key < 64 || squares.contains(key)).collect();
+        if odd.union(&squares).collect::<Vec<u32>>() == expected {
+            Ok(())
+        }
+        else {
+            Err(String::from("Union of the sets is wrong"))
+        }
+    }
+
+    #[test]
+    fn intersection() -> Result<(), String> {
+        let (odd, squares) = init();
+        let expected: Vec<u32> = vec![1, 9, 25, 49];
+        if odd.intersection(&squares).collect::<Vec<u32>>() == expected
+            && squares.intersection(&odd).collect::<Vec<u32>>() == expected {
+            Ok(())
+        }
+        else {
+            Err(String::from("Intersection of the sets is wrong"))
+        }
+    }
+
+    #[test]
+    fn difference() -> Result<(), String> {
+        let (odd, squares) = init();
+        let expected: Vec<u32> = (0..64).filter(|key| key % 2 == 1 && ![1, 9, 25, 49].contains(key)).collect();
+        if odd.difference(&squares).collect::<Vec<u32>>() == expected {
+            Ok(())
+        }
+        else {
+            Err(String::from("Difference of the sets is wrong"))
+        }
+    }
+
+    #[test]
+    fn symmetric_difference() -> Result<(), String> {
+        let (odd, squares) = init();
+        let expected: Vec<u32> = odd.union(&squares).filter(|key| !(odd.contains(key) && squares.contains(key))).collect();
+        if odd.symmetric_difference(&squares).collect::<Vec<u32>>() == expected {
+            Ok(())
+        }
+        else {
+            Err(String::from("Symmetric difference of the sets is wrong"))
+        }
+    }
+
+    #[test]
+    fn tries_with_different_values() -> Result<(), String> {
+        let mut names: Xfast<u8, &str> = Xfast::new(u8::MAX);
+        let mut counts: Xfast<u8, usize> = Xfast::new(u8::MAX);
+        names.insert(1, "one");
+        names.insert(200, "two hundred");
+        counts.insert(200, 2);
+        counts.insert(255, 1);
+        if names.intersection(&counts).collect::<Vec<u8>>() == vec![200] && names.union(&counts).count() == 3 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Set algebra over tries with different values is wrong"))
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use std::array;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+use crate::TrieNode;
+
+/// Index of a node in the arena of its trie
+pub(crate) type NodeId = u32;
+
+// the slots of a shared arena are grouped in pages of 64 nodes, the unit of copy on write, and the pages in
+// chunks of 1024 pages
+const PAGE_BITS: usize = 6;
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+const CHUNK_BITS: usize = 10;
+const CHUNK_SIZE: usize = 1 << CHUNK_BITS;
+
+type Page<K, T> = [Option<TrieNode<K, T>>; PAGE_SIZE];
+type Chunk<K, T> = Vec<Arc<Page<K, T>>>;
+
+/// Storage of the nodes of a trie, either `Arena` or `SharedArena`.
+///
+/// The trait is sealed, as its methods are only meant for the trie itself.
+pub trait Storage<K, T>: Index<NodeId, Output = TrieNode<K, T>> + IndexMut<NodeId> {
+    /// Whether the levels of the trie are shared with its snapshots, along with its nodes
+    const SHARED: bool;
+
+    /// Stores `node` in a free slot of the arena and returns its index
+    fn insert(&mut self, node: TrieNode<K, T>) -> NodeId;
+
+    /// Takes the node at `id` out of the arena and puts its slot on the free list
+    fn remove(&mut self, id: NodeId) -> TrieNode<K, T>;
+
+    /// Returns a pointer to the slot at `id` for writing, taken without borrowing any other slot. `owned` lists
+    /// the pages of a shared arena made its own by the calls before.
+    fn slot_mut_ptr(&mut self, id: NodeId, owned: &mut HashSet<usize>) -> *mut Option<TrieNode<K, T>>;
+}
+
+/// Storage of the nodes of a trie owned by the trie alone, the storage of `Xfast` by default.
+///
+/// The nodes link to each other by their index in the arena instead of by pointer. The slots of removed nodes
+/// are kept on a free list and handed out again to the next inserted nodes, so a trie with as many deletions
+/// as insertions doesn't grow its arena.
+pub struct Arena<K, T> {
+    slots: Vec<Option<TrieNode<K, T>>>,
+    free: Vec<NodeId>,
+}
+
+impl<K, T> Arena<K, T> {
+    /// Creates an empty arena
+    pub fn new() -> Self {
+        Arena {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<K, T> Default for Arena<K, T> {
+    fn default() -> Self {
+        Arena::new()
+    }
+}
+
+impl<K, T> Storage<K, T> for Arena<K, T> {
+    const SHARED: bool = false;
+
+    fn insert(&mut self, node: TrieNode<K, T>) -> NodeId {
+        match self.free.pop() {
+            Some(id) => {
+                self.slots[id as usize] = Some(node);
+                id
+            }
+            None => {
+                let id = NodeId::try_from(self.slots.len()).expect("a trie stores at most u32::MAX nodes");
+                self.slots.push(Some(node));
+                id
+            }
+        }
+    }
+
+    fn remove(&mut self, id: NodeId) -> TrieNode<K, T> {
+        let node = self.slots[id as usize].take().expect("removed node is stored in the arena");
+        self.free.push(id);
+        node
+    }
+
+    fn slot_mut_ptr(&mut self, id: NodeId, _owned: &mut HashSet<usize>) -> *mut Option<TrieNode<K, T>> {
+        assert!((id as usize) < self.slots.len(), "linked node is stored in the arena");
+        // `as_mut_ptr` doesn't borrow the slots, unlike indexing them
+        self.slots.as_mut_ptr().wrapping_add(id as usize)
+    }
+}
+
+impl<K, T> Index<NodeId> for Arena<K, T> {
+    type Output = TrieNode<K, T>;
+
+    fn index(&self, id: NodeId) -> &TrieNode<K, T> {
+        self.slots[id as usize].as_ref().expect("linked node is stored in the arena")
+    }
+}
+
+impl<K, T> IndexMut<NodeId> for Arena<K, T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut TrieNode<K, T> {
+        self.slots[id as usize].as_mut().expect("linked node is stored in the arena")
+    }
+}
+
+/// Storage of the nodes of a trie shared with the snapshots of the trie, see `Xfast::snapshot`.
+///
+/// The slots live in reference counted pages, held by reference counted chunks of pages, which a snapshot of
+/// the trie shares with the trie. Writing a node of a shared page first copies that page along with the table
+/// of its chunk and the table of chunks, so a write after a snapshot copies the pages it touches and a table
+/// of at most 1024 pointers per level of the directory. The copies clone the nodes, so the values of the trie
+/// must be `Clone`.
+///
+/// As a snapshot may be sent to another thread while the trie still shares its nodes, a trie storing its nodes
+/// in a shared arena is `Send` only when its values are both `Send` and `Sync`:
+/// ```compile_fail
+///     use std::cell::RefCell;
+///     use xfast::{SharedArena, Xfast};
+///
+///     let mut test_trie = Xfast::new_in(31_usize, SharedArena::new());
+///     test_trie.insert_key(11, RefCell::new("eleven"));
+///     std::thread::spawn(move || test_trie.len());
+/// ```
+pub struct SharedArena<K, T> {
+    chunks: Arc<Vec<Arc<Chunk<K, T>>>>,
+    len: usize,
+    free: Vec<NodeId>,
+}
+
+fn split_id(id: NodeId) -> (usize, usize, usize) {
+    let id = id as usize;
+    (id >> (PAGE_BITS + CHUNK_BITS), (id >> PAGE_BITS) & (CHUNK_SIZE - 1), id & (PAGE_SIZE - 1))
+}
+
+impl<K, T> SharedArena<K, T> {
+    /// Creates an empty arena
+    pub fn new() -> Self {
+        SharedArena {
+            chunks: Arc::new(Vec::new()),
+            len: 0,
+            free: Vec::new(),
+        }
+    }
+
+    /// Returns an arena sharing the chunks and the pages of this one, for a read-only snapshot of the trie
+    pub(crate) fn share(&self) -> Self {
+        SharedArena {
+            chunks: Arc::clone(&self.chunks),
+            len: self.len,
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<K: Clone, T: Clone> SharedArena<K, T> {
+    // returns the page at `page` of the chunk at `chunk` for writing, copying it first if a snapshot shares it
+    fn page_mut(&mut self, chunk: usize, page: usize) -> &mut Page<K, T> {
+        let chunk = Arc::make_mut(&mut Arc::make_mut(&mut self.chunks)[chunk]);
+        Arc::make_mut(&mut chunk[page])
+    }
+}
+
+impl<K, T> Default for SharedArena<K, T> {
+    fn default() -> Self {
+        SharedArena::new()
+    }
+}
+
+impl<K: Clone, T: Clone> Storage<K, T> for SharedArena<K, T> {
+    const SHARED: bool = true;
+
+    fn insert(&mut self, node: TrieNode<K, T>) -> NodeId {
+        match self.free.pop() {
+            Some(id) => {
+                let (chunk, page, slot) = split_id(id);
+                self.page_mut(chunk, page)[slot] = Some(node);
+                id
+            }
+            None => {
+                // the last id is never used, the shared levels mark their empty slots with it
+                let id = NodeId::try_from(self.len).ok().filter(|&id| id < NodeId::MAX);
+                let id = id.expect("a trie stores at most u32::MAX nodes");
+                let (chunk, page, slot) = split_id(id);
+                if slot == 0 {
+                    let chunks = Arc::make_mut(&mut self.chunks);
+                    if page == 0 {
+                        chunks.push(Arc::new(Vec::with_capacity(CHUNK_SIZE)));
+                    }
+                    Arc::make_mut(&mut chunks[chunk]).push(Arc::new(array::from_fn(|_| None)));
+                }
+                self.page_mut(chunk, page)[slot] = Some(node);
+                self.len += 1;
+                id
+            }
+        }
+    }
+
+    fn remove(&mut self, id: NodeId) -> TrieNode<K, T> {
+        let (chunk, page, slot) = split_id(id);
+        let node = self.page_mut(chunk, page)[slot].take().expect("removed node is stored in the arena");
+        self.free.push(id);
+        node
+    }
+
+    // the page is copied first if a snapshot shares it, the first time only. The pointer is then taken from the
+    // page without borrowing it, since the rest of the page may hold nodes borrowed elsewhere.
+    fn slot_mut_ptr(&mut self, id: NodeId, owned: &mut HashSet<usize>) -> *mut Option<TrieNode<K, T>> {
+        let (chunk, page, slot) = split_id(id);
+        if owned.insert(id as usize >> PAGE_BITS) {
+            self.page_mut(chunk, page);
+        }
+        Arc::as_ptr(&self.chunks[chunk][page]).cast_mut().cast::<Option<TrieNode<K, T>>>().wrapping_add(slot)
+    }
+}
+
+impl<K, T> Index<NodeId> for SharedArena<K, T> {
+    type Output = TrieNode<K, T>;
+
+    fn index(&self, id: NodeId) -> &TrieNode<K, T> {
+        let (chunk, page, slot) = split_id(id);
+        self.chunks[chunk][page][slot].as_ref().expect("linked node is stored in the arena")
+    }
+}
+
+impl<K: Clone, T: Clone> IndexMut<NodeId> for SharedArena<K, T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut TrieNode<K, T> {
+        let (chunk, page, slot) = split_id(id);
+        self.page_mut(chunk, page)[slot].as_mut().expect("linked node is stored in the arena")
+    }
+}
+
+/// Mutable walk along the linked leaves from a first to a last leaf, from both ends towards each other.
+///
+/// The borrow checker can't tell that following the links hands out every leaf once, so the walk borrows each
+/// leaf through a pointer to its own slot, like the mutable iterator of `LinkedList` in std. This is sound as:
+/// - the arena stays borrowed mutably by the walk, so no node is added, removed or moved meanwhile
+/// - the slots are reached by pointer and borrowed alone. Only a page of a shared arena is borrowed whole, the
+///   first time the walk reaches it and before any of its leaves is handed out, to copy it if a snapshot shares it.
+/// - the links of a leaf are read before the leaf is handed out, and the walk stops once both ends meet, so no
+///   leaf is handed out twice.
+pub(crate) struct LeavesMut<'a, K, T, A> {
+    arena: &'a mut A,
+    front: Option<NodeId>,
+    back: Option<NodeId>,
+    // pages the walk reached already, which a shared arena owns alone from then on
+    owned: HashSet<usize>,
+    marker: PhantomData<&'a mut TrieNode<K, T>>,
+}
+
+impl<'a, K, T, A: Storage<K, T>> LeavesMut<'a, K, T, A> {
+    /// Walks the leaves from the first to the last of `ends`, the last leaf being reachable from the first one
+    pub(crate) fn new(arena: &'a mut A, ends: Option<(NodeId, NodeId)>) -> Self {
+        LeavesMut {
+            arena,
+            front: ends.map(|(front, _)| front),
+            back: ends.map(|(_, back)| back),
+            owned: HashSet::new(),
+            marker: PhantomData,
+        }
+    }
+
+    fn leaf(&mut self, id: NodeId) -> &'a mut TrieNode<K, T> {
+        let slot = self.arena.slot_mut_ptr(id, &mut self.owned);
+        // the slot is valid for 'a and no other reference to it exists, see above
+        unsafe { (*slot).as_mut() }.expect("linked node is stored in the arena")
+    }
+
+    pub(crate) fn next_front(&mut self) -> Option<&'a mut TrieNode<K, T>> {
+        let leaf = self.leaf(self.front?);
+        // both ends met at the last leaf of the walk
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        }
+        else {
+            self.front = leaf.right;
+        }
+        Some(leaf)
+    }
+
+    pub(crate) fn next_back(&mut self) -> Option<&'a mut TrieNode<K, T>> {
+        let leaf = self.leaf(self.back?);
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        }
+        else {
+            self.back = leaf.left;
+        }
+        Some(leaf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{Arena, LeavesMut, SharedArena, Storage};
+    use crate::TrieNode;
+
+    #[test]
+    fn free_slots_are_reused() -> Result<(), String> {
+        let mut arena: Arena<u8, &str> = Arena::new();
+        let one = arena.insert(TrieNode::new_leaf(1, "one", 8));
+        let two = arena.insert(TrieNode::new_leaf(2, "two", 8));
+        arena.remove(one);
+        let three = arena.insert(TrieNode::new_leaf(3, "three", 8));
+        if three == one && arena.slots.len() == 2 && arena[two].key() == 2 && arena[three].key() == 3 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Free slot of the arena is not reused"))
+        }
+    }
+
+    #[test]
+    fn walk_leaves_from_both_ends() -> Result<(), String> {
+        let mut arena: SharedArena<u8, u8> = SharedArena::new();
+        // link the leaves out of the order of their slots, across several pages
+        let ids: Vec<u32> = (0..200).map(|key| arena.insert(TrieNode::new_leaf(199 - key, 0, 8))).collect();
+        for pair in ids.windows(2).rev() {
+            arena[pair[1]].right = Some(pair[0]);
+            arena[pair[0]].left = Some(pair[1]);
+        }
+        let shared = arena.share();
+        let mut leaves = LeavesMut::new(&mut arena, Some((ids[199], ids[0])));
+        let mut keys = Vec::new();
+        while let Some(leaf) = leaves.next_front() {
+            *leaf.value_mut() = 1;
+            keys.push(leaf.key());
+            if let Some(leaf) = leaves.next_back() {
+                *leaf.value_mut() = 1;
+                keys.push(leaf.key());
+            }
+        }
+        keys.sort_unstable();
+        let updated = ids.iter().all(|&id| *arena[id].value() == 1 && *shared[id].value() == 0);
+        if keys == (0..200).collect::<Vec<u8>>() && updated {
+            Ok(())
+        }
+        else {
+            Err(String::from("Walk doesn't hand out every leaf once"))
+        }
+    }
+
+    #[test]
+    fn shared_pages_are_copied_on_write() -> Result<(), String> {
+        let mut arena: SharedArena<u8, &str> = SharedArena::new();
+        let ids: Vec<u32> = (0..130).map(|key| arena.insert(TrieNode::new_leaf(key, "old", 8))).collect();
+        let shared = arena.share();
+        *arena[ids[100]].value_mut() = "new";
+        let copied: Vec<bool> = (0..3).map(|page| !Arc::ptr_eq(&arena.chunks[0][page], &shared.chunks[0][page])).collect();
+        if *shared[ids[100]].value() == "old" && *arena[ids[100]].value() == "new" && copied == vec![false, true, false] {
+            Ok(())
+        }
+        else {
+            Err(String::from("Write to a shared arena is not isolated to its page"))
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::cell::UnsafeCell;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::{Xfast, XfastError, XfastKey};
+
+/// A trie shared by many reader threads and written by one thread at a time.
+///
+/// The trie is kept twice, in the left-right fashion: readers are always directed to one copy while the writer
+/// updates the other one, then the writer publishes the updated copy with a single atomic store and replays
+/// the update on the copy the readers just left. Readers never take a lock and never wait for the writer, they
+/// only retry entering a copy if the writer switched copies at that very moment. The writer on the other hand
+/// waits for the readers of the copy it is about to update, so read guards are meant to be short lived, and a
+/// thread writing to the trie while it holds a read guard deadlocks, see `read`.
+///
+/// Every value is stored in both copies, hence values have to be `Clone` and the trie takes twice the memory of
+/// an `Xfast`.
+/// # Examples
+/// ```
+///     use std::sync::Arc;
+///     use std::thread;
+///     use xfast::ConcurrentXfast;
+///
+///     let routes: Arc<ConcurrentXfast<u32, &str>> = Arc::new(ConcurrentXfast::new(u32::MAX));
+///     routes.insert_key(10, "ten");
+///     let reader = {
+///         let routes = Arc::clone(&routes);
+///         thread::spawn(move || routes.find_predecessor(15))
+///     };
+///     assert_eq!(reader.join().unwrap(), Some((10, "ten")));
+/// ```
+pub struct ConcurrentXfast<K = usize, T = String, S = RandomState> {
+    tries: [UnsafeCell<Xfast<K, T, S>>; 2],
+    // index of the copy new readers enter
+    read_index: AtomicUsize,
+    // count of readers inside each copy
+    readers: [AtomicUsize; 2],
+    // serializes the writers
+    writer: Mutex<()>,
+}
+
+// A copy is only written by the writer holding the lock, after the readers of that copy have left and while new
+// readers are directed to the other copy, so shared references handed to readers never alias a mutation.
+unsafe impl<K: XfastKey + Send + Sync, T: Send + Sync, S: Send + Sync> Sync for ConcurrentXfast<K, T, S> {}
+
+impl<K: XfastKey, T: Clone> ConcurrentXfast<K, T> {
+    /// Creates a new concurrent trie to store a given `range` of integers
+    pub fn new(range: K) -> Self {
+        Self::from(Xfast::new(range))
+    }
+}
+
+impl<K: XfastKey, T: Clone, S: BuildHasher + Clone> ConcurrentXfast<K, T, S> {
+    /// Creates a new concurrent trie to store a given `range` of integers, hashing the prefixes of the keys with
+    /// `hasher`
+    pub fn with_hasher(range: K, hasher: S) -> Self {
+        Self::from(Xfast::with_hasher(range, hasher))
+    }
+}
+
+impl<K: XfastKey, T: Clone, S: BuildHasher + Clone> From<Xfast<K, T, S>> for ConcurrentXfast<K, T, S> {
+    fn from(trie: Xfast<K, T, S>) -> Self {
+        ConcurrentXfast {
+            tries: [UnsafeCell::new(trie.clone()), UnsafeCell::new(trie)],
+            read_index: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            writer: Mutex::new(()),
+        }
+    }
+}
+
+impl<K: XfastKey, T: Clone, S: BuildHasher> ConcurrentXfast<K, T, S> {
+    /// Returns a guard dereferencing to the current version of the trie, to run several queries on the same
+    /// version or to iterate over it.
+    ///
+    /// The writer waits for the guard to be dropped before it updates the copy the guard reads. It spins until
+    /// then without releasing the write lock, so a thread must drop its guards before it writes: an `insert_key`
+    /// or a `delete_key` called while the same thread holds a guard, or a guard taken by the same thread from
+    /// inside a write, like from the hasher of the trie, deadlocks.
+    /// # Examples
+    /// ```
+    ///     use xfast::ConcurrentXfast;
+    ///
+    ///     let test_trie: ConcurrentXfast<usize, &str> = ConcurrentXfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     let keys: Vec<usize> = test_trie.read().iter().map(|(&key, _)| key).collect();
+    ///     assert_eq!(keys, vec![1, 11]);
+    /// ```
+    pub fn read(&self) -> XfastReadGuard<'_, K, T, S> {
+        loop {
+            let index = self.read_index.load(Ordering::SeqCst);
+            self.readers[index].fetch_add(1, Ordering::SeqCst);
+            // the writer may have switched copies between the load and the registration, in which case it may
+            // already be updating this copy
+            if self.read_index.load(Ordering::SeqCst) == index {
+                return XfastReadGuard {
+                    trie: unsafe { &*self.tries[index].get() },
+                    readers: &self.readers[index],
+                };
+            }
+            self.readers[index].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Returns the count of values stored in the trie
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns true if the trie stores no values
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Returns the largest key less than or equal to `key` along with its value
+    pub fn find_predecessor(&self, key: K) -> Option<(K, T)> {
+        self.read().find_predecessor(key).map(|node| (node.key(), node.value().clone()))
+    }
+
+    /// Returns the smallest key more than or equal to `key` along with its value
+    pub fn find_successor(&self, key: K) -> Option<(K, T)> {
+        self.read().find_successor(key).map(|node| (node.key(), node.value().clone()))
+    }
+
+    /// Returns the value stored with `key`
+    pub fn find_key(&self, key: K) -> Option<T> {
+        self.read().get(&key).cloned()
+    }
+
+    // runs `update` on the copy the readers left, publishes it and runs `replay`, the same update, on the other
+    // copy. Anything able to panic, like cloning the value, must happen before, as a panic between the two
+    // updates leaves the copies apart: the lock is poisoned then and every later write panics.
+    fn write<R>(&self, update: impl FnOnce(&mut Xfast<K, T, S>) -> R, replay: impl FnOnce(&mut Xfast<K, T, S>) -> R)
+        -> R {
+        let _writer = self.writer.lock().expect("a writer panicked between the updates of the two copies");
+        let published = self.read_index.load(Ordering::SeqCst);
+        let hidden = 1 - published;
+        self.wait_for_readers(hidden);
+        update(unsafe { &mut *self.tries[hidden].get() });
+        self.read_index.store(hidden, Ordering::SeqCst);
+        self.wait_for_readers(published);
+        replay(unsafe { &mut *self.tries[published].get() })
+    }
+
+    fn wait_for_readers(&self, index: usize) {
+        while self.readers[index].load(Ordering::SeqCst) != 0 {
+            thread::yield_now();
+        }
+    }
+
+    /// Insert `key` and `value` into the trie and publish the update to the readers.
+    ///
+    /// If the trie already has the `key`, its value is replaced and the old value is returned,
+    /// otherwise None is returned.
+    ///
+    /// # Panics
+    /// Panics if `key` is larger than the range of the trie.
+    pub fn insert_key(&self, key: K, value: T) -> Option<T> {
+        match self.try_insert(key, value) {
+            Ok(old_value) => old_value,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Insert `key` and `value` into the trie and publish the update to the readers, reporting keys larger than
+    /// the range of the trie as an error instead of panicking.
+    pub fn try_insert(&self, key: K, value: T) -> Result<Option<T>, XfastError<K>> {
+        // each copy stores its own value, cloned before any copy is updated
+        let copy = value.clone();
+        self.write(move |trie| trie.try_insert(key, copy), move |trie| trie.try_insert(key, value))
+    }
+
+    /// Delete `key` from the trie and publish the update to the readers. If the key doesn't exist it returns
+    /// None else returns the value stored with the deleted key.
+    pub fn delete_key(&self, key: K) -> Option<T> {
+        self.write(|trie| trie.delete_key(key), |trie| trie.delete_key(key))
+    }
+}
+
+/// Read access to a version of a `ConcurrentXfast`, the version doesn't change as long as the guard lives.
+///
+/// This is constructed from the `read` method on `ConcurrentXfast`.
+pub struct XfastReadGuard<'a, K, T, S = RandomState> {
+    trie: &'a Xfast<K, T, S>,
+    readers: &'a AtomicUsize,
+}
+
+impl<'a, K, T, S> Deref for XfastReadGuard<'a, K, T, S> {
+    type Target = Xfast<K, T, S>;
+
+    fn deref(&self) -> &Xfast<K, T, S> {
+        self.trie
+    }
+}
+
+impl<'a, K, T, S> Drop for XfastReadGuard<'a, K, T, S> {
+    fn drop(&mut self) {
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    use super::ConcurrentXfast;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_and_sync() {
+        assert_send_sync::<ConcurrentXfast<u64, String>>();
+        assert_send_sync::<super::XfastReadGuard<'static, u64, String>>();
+    }
+
+    // a value which panics when cloned a second time
+    struct Fragile(Rc<Cell<usize>>);
+
+    impl Fragile {
+        fn new() -> Self {
+            Fragile(Rc::new(Cell::new(0)))
+        }
+    }
+
+    impl Clone for Fragile {
+        fn clone(&self) -> Self {
+            self.0.set(self.0.get() + 1);
+            if self.0.get() > 1 {
+                panic!("fragile value cloned twice");
+            }
+            Fragile(Rc::clone(&self.0))
+        }
+    }
+
+    #[test]
+    fn insert_clones_the_value_once() -> Result<(), String> {
+        let test_trie: ConcurrentXfast<u8, Fragile> = ConcurrentXfast::new(u8::MAX);
+        test_trie.insert_key(1, Fragile::new());
+        test_trie.insert_key(2, Fragile::new());
+        // consecutive writes publish the two copies in turn
+        let first_len = test_trie.len();
+        test_trie.insert_key(3, Fragile::new());
+        let second_len = test_trie.len();
+        test_trie.delete_key(3);
+        if first_len == 2 && second_len == 3 && test_trie.len() == 2 && test_trie.read().contains_key(&2) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Copies of the concurrent trie diverged"))
+        }
+    }
+
+    #[test]
+    fn readers_see_published_updates() -> Result<(), String> {
+        let test_trie: Arc<ConcurrentXfast<u32, u32>> = Arc::new(ConcurrentXfast::new(1023));
+        let done = Arc::new(AtomicBool::new(false));
+        let readers: Vec<_> = (0..4).map(|_| {
+            let test_trie = Arc::clone(&test_trie);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let mut consistent = true;
+                while !done.load(Ordering::SeqCst) {
+                    // the writer only stores even keys with their halves as values
+                    if let Some((key, value)) = test_trie.find_predecessor(999) {
+                        consistent &= key % 2 == 0 && value == key / 2;
+                    }
+                    let version = test_trie.read();
+                    consistent &= version.iter().count() == version.len();
+                }
+                consistent
+            })
+        }).collect();
+
+        for key in (0..1000).step_by(2) {
+            test_trie.insert_key(key, key / 2);
+        }
+        for key in (0..1000).step_by(4) {
+            test_trie.delete_key(key);
+        }
+        done.store(true, Ordering::SeqCst);
+        for reader in readers {
+            if !reader.join().map_err(|_| String::from("Reader thread panicked"))? {
+                return Err(String::from("Reader saw an inconsistent trie"));
+            }
+        }
+
+        let keys: Vec<u32> = test_trie.read().iter().map(|(&key, _)| key).collect();
+        let expected: Vec<u32> = (0..1000).filter(|key| key % 4 == 2).collect();
+        if keys == expected && test_trie.find_successor(3) == Some((6, 3)) && test_trie.find_key(4).is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Concurrent trie is wrong after the updates"))
+        }
+    }
+}
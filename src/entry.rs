@@ -0,0 +1,223 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use crate::{Arena, NodeId, Storage, Xfast, XfastKey};
+
+/// A view into a single key of a trie, which is either vacant or occupied.
+///
+/// This is constructed from the `entry` method on `Xfast`.
+pub enum Entry<'a, K: XfastKey, T, S = RandomState, A = Arena<K, T>> {
+    /// A key stored in the trie
+    Occupied(OccupiedEntry<'a, K, T, S, A>),
+    /// A key missing from the trie
+    Vacant(VacantEntry<'a, K, T, S, A>),
+}
+
+/// A view into a key stored in the trie, part of the `Entry` enum.
+pub struct OccupiedEntry<'a, K: XfastKey, T, S = RandomState, A = Arena<K, T>> {
+    trie: &'a mut Xfast<K, T, S, A>,
+    node: NodeId,
+}
+
+/// A view into a key missing from the trie, part of the `Entry` enum.
+pub struct VacantEntry<'a, K: XfastKey, T, S = RandomState, A = Arena<K, T>> {
+    trie: &'a mut Xfast<K, T, S, A>,
+    key: K,
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> Entry<'a, K, T, S, A> {
+    pub(crate) fn new(trie: &'a mut Xfast<K, T, S, A>, key: K) -> Self {
+        match trie.find_key_id(key) {
+            Some(node) => Entry::Occupied(OccupiedEntry { trie, node }),
+            None => Entry::Vacant(VacantEntry { trie, key }),
+        }
+    }
+
+    /// Returns the key of the entry
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     assert_eq!(test_trie.entry(11).key(), 11);
+    /// ```
+    pub fn key(&self) -> K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts `default` for a vacant key and returns a mutable reference to the value of the key
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, usize> = Xfast::new(31);
+    ///     *test_trie.entry(11).or_insert(0) += 1;
+    ///     *test_trie.entry(11).or_insert(0) += 1;
+    ///     assert_eq!(test_trie.get(&11), Some(&2));
+    /// ```
+    pub fn or_insert(self, default: T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Inserts the result of `default` for a vacant key and returns a mutable reference to the value of the key
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, String> = Xfast::new(31);
+    ///     test_trie.entry(11).or_insert_with(|| String::from("eleven"));
+    ///     test_trie.entry(11).or_insert_with(|| String::from("XI"));
+    ///     assert_eq!(test_trie.get(&11), Some(&String::from("eleven")));
+    /// ```
+    pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Inserts the result of `default` called with the key for a vacant key and returns a mutable reference to
+    /// the value of the key
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, usize> = Xfast::new(31);
+    ///     test_trie.entry(11).or_insert_with_key(|key| key * 2);
+    ///     assert_eq!(test_trie.get(&11), Some(&22));
+    /// ```
+    pub fn or_insert_with_key<F: FnOnce(K) -> T>(self, default: F) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Calls `f` on the value of an occupied key before any insertion into the trie
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, usize> = Xfast::new(31);
+    ///     test_trie.entry(11).and_modify(|count| *count += 1).or_insert(1);
+    ///     test_trie.entry(11).and_modify(|count| *count += 1).or_insert(1);
+    ///     assert_eq!(test_trie.get(&11), Some(&2));
+    /// ```
+    pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K: XfastKey, T: Default, S: BuildHasher, A: Storage<K, T>> Entry<'a, K, T, S, A> {
+    /// Inserts the default value for a vacant key and returns a mutable reference to the value of the key
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, Vec<&str>> = Xfast::new(31);
+    ///     test_trie.entry(11).or_default().push("eleven");
+    ///     test_trie.entry(11).or_default().push("XI");
+    ///     assert_eq!(test_trie.get(&11), Some(&vec!["eleven", "XI"]));
+    /// ```
+    pub fn or_default(self) -> &'a mut T {
+        self.or_insert_with(T::default)
+    }
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> OccupiedEntry<'a, K, T, S, A> {
+    /// Returns the key of the entry
+    pub fn key(&self) -> K {
+        self.trie.arena[self.node].key()
+    }
+
+    /// Returns a reference to the value of the entry
+    pub fn get(&self) -> &T {
+        self.trie.arena[self.node].value()
+    }
+
+    /// Returns a mutable reference to the value of the entry
+    pub fn get_mut(&mut self) -> &mut T {
+        self.trie.arena[self.node].value_mut()
+    }
+
+    /// Converts the entry into a mutable reference to its value living as long as the trie borrow
+    pub fn into_mut(self) -> &'a mut T {
+        self.trie.arena[self.node].value_mut()
+    }
+
+    /// Replaces the value of the entry and returns the old value
+    /// # Examples
+    /// ```
+    ///     use xfast::{Entry, Xfast};
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     if let Entry::Occupied(mut entry) = test_trie.entry(11) {
+    ///         assert_eq!(entry.insert("XI"), "eleven");
+    ///     }
+    ///     assert_eq!(test_trie.get(&11), Some(&"XI"));
+    /// ```
+    pub fn insert(&mut self, value: T) -> T {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the key of the entry from the trie and returns its value
+    /// # Examples
+    /// ```
+    ///     use xfast::{Entry, Xfast};
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     if let Entry::Occupied(entry) = test_trie.entry(11) {
+    ///         assert_eq!(entry.remove(), "eleven");
+    ///     }
+    ///     assert!(test_trie.is_empty());
+    /// ```
+    pub fn remove(self) -> T {
+        let key = self.key();
+        self.trie.delete_key(key).expect("occupied entry missing from the trie")
+    }
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> VacantEntry<'a, K, T, S, A> {
+    /// Returns the key of the entry
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    /// Takes back the key of the entry
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts `value` with the key of the entry and returns a mutable reference to it
+    /// # Examples
+    /// ```
+    ///     use xfast::{Entry, Xfast};
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     if let Entry::Vacant(entry) = test_trie.entry(11) {
+    ///         entry.insert("eleven");
+    ///     }
+    ///     assert_eq!(test_trie.get(&11), Some(&"eleven"));
+    /// ```
+    pub fn insert(self, value: T) -> &'a mut T {
+        let node = self.trie.insert_leaf(self.key, value);
+        self.trie.arena[node].value_mut()
+    }
+}
@@ -0,0 +1,42 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::XfastKey;
+
+/// Errors reported by the checked operations of the trie
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum XfastError<K = usize> {
+    /// The `key` is larger than `max`, the largest key the trie was created to store
+    KeyOutOfRange {
+        key: K,
+        max: K,
+    },
+    /// The `key` came after the larger key `previous` in input required to be sorted
+    UnsortedKey {
+        key: K,
+        previous: K,
+    },
+    /// The `key` came twice in input required to have distinct keys
+    DuplicateKey {
+        key: K,
+    },
+}
+
+impl<K: XfastKey> fmt::Display for XfastError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XfastError::KeyOutOfRange { key, max } => {
+                write!(f, "key {} is out of range, the trie stores keys up to {}", key, max)
+            }
+            XfastError::UnsortedKey { key, previous } => {
+                write!(f, "key {} comes after the larger key {}, the keys are not sorted", key, previous)
+            }
+            XfastError::DuplicateKey { key } => {
+                write!(f, "key {} is present more than once", key)
+            }
+        }
+    }
+}
+
+impl<K: XfastKey> Error for XfastError<K> {}
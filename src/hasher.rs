@@ -0,0 +1,104 @@
+use std::convert::TryInto;
+use std::hash::{BuildHasherDefault, Hasher};
+
+// odd multiplier with the bits spread evenly, the same one as the Fx hasher of rustc
+const SEED: u64 = 0xf135_7aea_2e62_a9c5;
+
+/// A fast hasher for the integer prefixes stored at the levels of a trie.
+///
+/// Every integer written to the hasher is added to the state, which is then multiplied by a large odd
+/// constant, in the style of the Fx hasher of rustc. The multiplication moves the entropy of the key to the
+/// high bits of the state, so the state is rotated when the hash is read to bring it back to the low bits used
+/// to pick a bucket of the hash map.
+///
+/// The hasher takes no random seed, so anyone choosing the keys can make them collide. Use it through
+/// `BuildIntegerHasher` for keys coming from a trusted source only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IntegerHasher {
+    hash: u64,
+}
+
+impl IntegerHasher {
+    #[inline]
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = self.hash.wrapping_add(word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for IntegerHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add_to_hash(u64::from_le_bytes(chunk.try_into().expect("chunks of eight bytes")));
+        }
+        let mut tail = [0; 8];
+        let remainder = chunks.remainder();
+        tail[..remainder.len()].copy_from_slice(remainder);
+        self.add_to_hash(u64::from_le_bytes(tail) ^ remainder.len() as u64);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, key: u8) {
+        self.add_to_hash(key as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, key: u16) {
+        self.add_to_hash(key as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, key: u32) {
+        self.add_to_hash(key as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, key: u64) {
+        self.add_to_hash(key);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, key: u128) {
+        self.add_to_hash(key as u64);
+        self.add_to_hash((key >> 64) as u64);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, key: usize) {
+        self.add_to_hash(key as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash.rotate_left(26)
+    }
+}
+
+/// Builds an `IntegerHasher` for each level of a trie, see `Xfast::with_integer_hasher`
+pub type BuildIntegerHasher = BuildHasherDefault<IntegerHasher>;
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::hash::{BuildHasher, Hash};
+
+    use super::BuildIntegerHasher;
+
+    fn hash<K: Hash>(key: K) -> u64 {
+        BuildIntegerHasher::default().hash_one(key)
+    }
+
+    #[test]
+    fn high_bits_reach_the_buckets() -> Result<(), String> {
+        // keys differing in their high bits only must still spread over the low bits of the hash
+        let buckets: HashSet<u64> = (0..64_u64).map(|shift| hash(1_u128 << (shift + 64)) & 0xff).collect();
+        let low_buckets: HashSet<u64> = (0..64_u64).map(|shift| hash(1_u64 << shift) & 0xff).collect();
+        if buckets.len() > 32 && low_buckets.len() > 32 && hash(1_u64) != hash(2_u64) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Integer hasher leaves the low bits of the hash unused"))
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// Unsigned integer types which can be stored as keys of a trie.
+///
+/// The trie works on the binary representation of the keys, each level of the trie consumes one
+/// bit of the key starting from the most significant one. The width of the key type bounds the
+/// height of the trie, so a `u32` trie never needs more than 32 levels.
+pub trait XfastKey: Copy + Eq + Ord + Hash + Debug + Display {
+    /// Number of bits in the key type
+    const BITS: usize;
+
+    /// The smallest key
+    const MIN: Self;
+
+    /// The largest key
+    const MAX: Self;
+
+    /// Returns the prefix of the key left after dropping its `shift` least significant bits.
+    /// Shifting by the width of the key or more yields the zero prefix.
+    fn prefix(self, shift: usize) -> Self;
+
+    /// Returns true if the bit at `index` is set, index 0 being the least significant bit.
+    fn bit(self, index: usize) -> bool;
+
+    /// Returns the number of bits needed to represent the key.
+    fn bit_length(self) -> usize;
+}
+
+macro_rules! impl_xfast_key {
+    ($($key:ty),*) => {
+        $(
+            impl XfastKey for $key {
+                const BITS: usize = <$key>::BITS as usize;
+                const MIN: Self = <$key>::MIN;
+                const MAX: Self = <$key>::MAX;
+
+                #[inline]
+                fn prefix(self, shift: usize) -> Self {
+                    if shift >= <Self as XfastKey>::BITS {
+                        0
+                    }
+                    else {
+                        self >> shift
+                    }
+                }
+
+                #[inline]
+                fn bit(self, index: usize) -> bool {
+                    (self.prefix(index) & 1) != 0
+                }
+
+                #[inline]
+                fn bit_length(self) -> usize {
+                    <Self as XfastKey>::BITS - self.leading_zeros() as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_xfast_key!(u8, u16, u32, u64, u128, usize);
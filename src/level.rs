@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::mem;
+use std::ops::Index;
+use std::sync::Arc;
+
+use crate::{NodeId, XfastKey};
+
+// the shards are grouped in chunks of 64 shards, copied on write along with the shard written
+const CHUNK_BITS: u32 = 6;
+const CHUNK_SIZE: usize = 1 << CHUNK_BITS;
+// largest average count of prefixes per shard before the level doubles its shards
+const SHARD_LOAD: usize = 256;
+// fewest slots of a shard
+const MIN_SLOTS: usize = 8;
+// odd multiplier spreading every bit of a hash to the bits picking its shard
+const SPREAD: u64 = 0x9e37_79b9_7f4a_7c15;
+// node of the empty slots, an id the shared arena never hands out
+const EMPTY: NodeId = NodeId::MAX;
+
+// a prefix and its node, an `Option` would take more room as neither has a niche
+type Slot<K> = (K, NodeId);
+
+// table with linear probing, its slots counted by a power of two and at most 3/4 full
+#[derive(Clone)]
+struct Shard<K> {
+    len: usize,
+    slots: Arc<[Slot<K>]>,
+}
+
+type Chunk<K> = Vec<Shard<K>>;
+
+/// A level of the trie, mapping the prefixes of the keys at that level to their nodes in the arena.
+///
+/// A trie owning its nodes keeps each level in a hash map of its own. A trie sharing its nodes with its snapshots
+/// shares its levels with them too.
+#[derive(Clone)]
+pub(crate) enum Level<K, S> {
+    Owned(HashMap<K, NodeId, S>),
+    Shared(SharedLevel<K, S>),
+}
+
+impl<K: XfastKey, S: BuildHasher> Level<K, S> {
+    pub(crate) fn with_hasher(hasher: S, shared: bool) -> Self {
+        match shared {
+            true => Level::Shared(SharedLevel::with_hasher(hasher)),
+            false => Level::Owned(HashMap::with_hasher(hasher)),
+        }
+    }
+
+    pub(crate) fn hasher(&self) -> &S {
+        match self {
+            Level::Owned(level) => level.hasher(),
+            Level::Shared(level) => level.hasher(),
+        }
+    }
+
+    /// Returns the count of prefixes stored at the level
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Level::Owned(level) => level.len(),
+            Level::Shared(level) => level.len(),
+        }
+    }
+
+    pub(crate) fn get_key_value(&self, key: &K) -> Option<(&K, &NodeId)> {
+        match self {
+            Level::Owned(level) => level.get_key_value(key),
+            Level::Shared(level) => level.get_key_value(key),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<&NodeId> {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    pub(crate) fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Maps `key` to `value` and returns the node `key` was mapped to before
+    pub(crate) fn insert(&mut self, key: K, value: NodeId) -> Option<NodeId> {
+        match self {
+            Level::Owned(level) => level.insert(key, value),
+            Level::Shared(level) => level.insert(key, value),
+        }
+    }
+
+    /// Removes `key` from the level and returns the node it was mapped to
+    pub(crate) fn remove(&mut self, key: &K) -> Option<NodeId> {
+        match self {
+            Level::Owned(level) => level.remove(key),
+            Level::Shared(level) => level.remove(key),
+        }
+    }
+}
+
+impl<K: XfastKey, S: BuildHasher> Index<&K> for Level<K, S> {
+    type Output = NodeId;
+
+    fn index(&self, key: &K) -> &NodeId {
+        self.get(key).expect("prefix stored at the level")
+    }
+}
+
+/// A level of a trie sharing its nodes with its snapshots.
+///
+/// The level is a hash table split in shards of reference counted slots, held by reference counted chunks of
+/// shards like the pages of the shared arena, so that a snapshot of the trie shares the whole level in constant
+/// time. Writing a prefix of a shared level copies the slots of its shard, a few hundred prefixes, along with the
+/// table of the 64 shards of its chunk and the table of chunks, and leaves every other shard shared, where a hash
+/// map shared whole would be copied whole by the first write after each snapshot. The level doubles its shards
+/// once they hold 256 prefixes on average.
+pub(crate) struct SharedLevel<K, S> {
+    chunks: Arc<Vec<Arc<Chunk<K>>>>,
+    // the level has 2^bits shards
+    bits: u32,
+    len: usize,
+    hasher: S,
+}
+
+// index of the shard of a prefix hashed as `hash` among 2^bits shards
+fn shard_index(hash: u64, bits: u32) -> usize {
+    ((hash.wrapping_mul(SPREAD) >> 32) as usize) & ((1 << bits) - 1)
+}
+
+fn entries<K>(slots: &[Slot<K>]) -> impl Iterator<Item = &Slot<K>> {
+    slots.iter().filter(|(_, value)| *value != EMPTY)
+}
+
+// index of the slot holding `key`, or else of the empty slot ending its probe
+fn probe<K: XfastKey>(slots: &[Slot<K>], hash: u64, key: &K) -> Result<usize, usize> {
+    let mask = slots.len() - 1;
+    let mut index = hash as usize & mask;
+    loop {
+        match &slots[index] {
+            (_, EMPTY) => return Err(index),
+            (stored, _) if stored == key => return Ok(index),
+            _ => index = (index + 1) & mask,
+        }
+    }
+}
+
+// returns the shard of `hash` for writing, copying the tables holding it first if a snapshot shares them
+fn shard_mut<K: Clone>(chunks: &mut Arc<Vec<Arc<Chunk<K>>>>, bits: u32, hash: u64) -> &mut Shard<K> {
+    let shard = shard_index(hash, bits);
+    let chunk = Arc::make_mut(&mut Arc::make_mut(chunks)[shard >> CHUNK_BITS]);
+    &mut chunk[shard & (CHUNK_SIZE - 1)]
+}
+
+// builds a shard holding `entries`, with a quarter of its slots or more left empty
+fn new_shard<K: XfastKey, S: BuildHasher>(hasher: &S, entries: Vec<Slot<K>>) -> Shard<K> {
+    let mut slots = vec![(K::MIN, EMPTY); (entries.len() * 4 / 3 + 1).next_power_of_two().max(MIN_SLOTS)];
+    let len = entries.len();
+    for (key, value) in entries {
+        if let Err(index) = probe(&slots, hasher.hash_one(key), &key) {
+            slots[index] = (key, value);
+        }
+    }
+    Shard {
+        len,
+        slots: Arc::from(slots),
+    }
+}
+
+impl<K: XfastKey, S: BuildHasher> SharedLevel<K, S> {
+    pub(crate) fn with_hasher(hasher: S) -> Self {
+        SharedLevel {
+            chunks: Arc::new(vec![Arc::new(vec![new_shard(&hasher, Vec::new())])]),
+            bits: 0,
+            len: 0,
+            hasher,
+        }
+    }
+
+    pub(crate) fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    /// Returns the count of prefixes stored at the level
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    fn shard(&self, hash: u64) -> &Shard<K> {
+        let shard = shard_index(hash, self.bits);
+        &self.chunks[shard >> CHUNK_BITS][shard & (CHUNK_SIZE - 1)]
+    }
+
+    pub(crate) fn get_key_value(&self, key: &K) -> Option<(&K, &NodeId)> {
+        let hash = self.hasher.hash_one(key);
+        let slots = &self.shard(hash).slots;
+        let (key, value) = &slots[probe(slots, hash, key).ok()?];
+        Some((key, value))
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<&NodeId> {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    pub(crate) fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Maps `key` to `value` and returns the node `key` was mapped to before
+    pub(crate) fn insert(&mut self, key: K, value: NodeId) -> Option<NodeId> {
+        let hash = self.hasher.hash_one(key);
+        let shard = shard_mut(&mut self.chunks, self.bits, hash);
+        match probe(&shard.slots, hash, &key) {
+            Ok(index) => return Some(mem::replace(&mut Arc::make_mut(&mut shard.slots)[index].1, value)),
+            Err(index) => Arc::make_mut(&mut shard.slots)[index] = (key, value),
+        }
+        shard.len += 1;
+        if shard.len * 4 > shard.slots.len() * 3 {
+            *shard = new_shard(&self.hasher, entries(&shard.slots).copied().collect());
+        }
+        self.len += 1;
+        if self.len > SHARD_LOAD << self.bits {
+            self.grow();
+        }
+        None
+    }
+
+    /// Removes `key` from the level and returns the node it was mapped to
+    pub(crate) fn remove(&mut self, key: &K) -> Option<NodeId> {
+        let hash = self.hasher.hash_one(key);
+        // an absent key must not copy its shard
+        let mut hole = probe(&self.shard(hash).slots, hash, key).ok()?;
+        let shard = shard_mut(&mut self.chunks, self.bits, hash);
+        shard.len -= 1;
+        let slots = Arc::make_mut(&mut shard.slots);
+        let (_, value) = mem::replace(&mut slots[hole], (K::MIN, EMPTY));
+        // move back the prefixes probed past the emptied slot, so that no probe stops short of its prefix
+        let mask = slots.len() - 1;
+        let mut index = (hole + 1) & mask;
+        while slots[index].1 != EMPTY {
+            let home = self.hasher.hash_one(slots[index].0) as usize & mask;
+            if index.wrapping_sub(home) & mask >= index.wrapping_sub(hole) & mask {
+                slots[hole] = mem::replace(&mut slots[index], (K::MIN, EMPTY));
+                hole = index;
+            }
+            index = (index + 1) & mask;
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    // doubles the shards of the level, spreading the prefixes of each shard over two
+    fn grow(&mut self) {
+        let bits = self.bits + 1;
+        let mut spread: Vec<Vec<Slot<K>>> = (0..1 << bits).map(|_| Vec::new()).collect();
+        let shards = self.chunks.iter().flat_map(|chunk| chunk.iter());
+        for &(key, value) in shards.flat_map(|shard| entries(&shard.slots)) {
+            spread[shard_index(self.hasher.hash_one(key), bits)].push((key, value));
+        }
+        let mut shards = spread.into_iter().map(|entries| new_shard(&self.hasher, entries));
+        let chunks = (1_usize << bits).div_ceil(CHUNK_SIZE);
+        self.chunks = Arc::new((0..chunks).map(|_| Arc::new(shards.by_ref().take(CHUNK_SIZE).collect())).collect());
+        self.bits = bits;
+    }
+}
+
+/// Sharing a level copies no prefix, the copy and the level share all their shards until either one is written.
+impl<K, S: Clone> Clone for SharedLevel<K, S> {
+    fn clone(&self) -> Self {
+        SharedLevel {
+            chunks: Arc::clone(&self.chunks),
+            bits: self.bits,
+            len: self.len,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+    use std::sync::Arc;
+
+    use super::{Level, SharedLevel, SHARD_LOAD};
+    use crate::BuildIntegerHasher;
+
+    // hashes a prefix to itself, to choose the slots the prefixes probe from
+    #[derive(Default)]
+    struct SameHasher(u64);
+
+    impl Hasher for SameHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, _: &[u8]) {
+            unreachable!("prefixes are hashed as integers")
+        }
+
+        fn write_u64(&mut self, key: u64) {
+            self.0 = key;
+        }
+    }
+
+    #[test]
+    fn matches_a_hash_map() -> Result<(), String> {
+        for shared in [false, true].iter().copied() {
+            let mut level: Level<u64, BuildIntegerHasher> = Level::with_hasher(BuildIntegerHasher::default(), shared);
+            let mut expected = HashMap::new();
+            for key in 0..5000_u64 {
+                let key = key.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                if level.insert(key, key as u32 >> 1) != expected.insert(key, key as u32 >> 1) {
+                    return Err(String::from("Insert returns the wrong old node"));
+                }
+            }
+            for key in expected.keys().copied().step_by(3).collect::<Vec<u64>>() {
+                if level.remove(&key) != expected.remove(&key) {
+                    return Err(String::from("Remove returns the wrong node"));
+                }
+            }
+            let found = expected.iter().all(|(key, value)| level.get_key_value(key) == Some((key, value)));
+            if !found || level.len() != expected.len() || level.remove(&1).is_some() || level.contains_key(&1) {
+                return Err(String::from("Level lost track of its prefixes"));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn probes_wrap_around_the_shard() -> Result<(), String> {
+        // all of them but 0 probe from the last of the 8 slots of the only shard, and 0 is pushed past its own slot
+        let mut level: SharedLevel<u64, BuildHasherDefault<SameHasher>> = SharedLevel::with_hasher(Default::default());
+        for &key in &[7, 15, 0, 23] {
+            level.insert(key, key as u32);
+        }
+        let wrapped = level.chunks[0][0].slots.iter().map(|&(key, _)| key).collect::<Vec<u64>>() == vec![15, 0, 23, 0, 0, 0, 0, 7];
+        // removing 7 moves each of the others back by one slot, across the end of the shard
+        level.remove(&7);
+        let slots = &level.chunks[0][0].slots;
+        let moved = slots[7] == (15, 15) && slots[..2] == [(0, 0), (23, 23)][..];
+        if wrapped && moved && level.get(&7).is_none() && [15, 0, 23].iter().all(|key| level.get(key) == Some(&(*key as u32))) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Probe across the end of a shard loses prefixes"))
+        }
+    }
+
+    #[test]
+    fn emptied_level_takes_prefixes_again() -> Result<(), String> {
+        let mut level: SharedLevel<u32, BuildIntegerHasher> = SharedLevel::with_hasher(BuildIntegerHasher::default());
+        for round in 0..3 {
+            for key in 0..2000 {
+                level.insert(key, key + round);
+            }
+            // remove them in another order than inserted, to move prefixes back through every probe
+            for key in (0..2000).rev() {
+                if level.remove(&key) != Some(key + round) {
+                    return Err(String::from("Remove returns the wrong node"));
+                }
+            }
+            if level.len() != 0 || (0..2000).any(|key| level.contains_key(&key)) {
+                return Err(String::from("Emptied level still holds prefixes"));
+            }
+        }
+        let shards = level.chunks.iter().flat_map(|chunk| chunk.iter());
+        if shards.map(|shard| shard.len).sum::<usize>() == 0 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Shards of an emptied level count prefixes"))
+        }
+    }
+
+    #[test]
+    fn growth_keeps_the_shared_level() -> Result<(), String> {
+        let mut level: SharedLevel<u32, BuildIntegerHasher> = SharedLevel::with_hasher(BuildIntegerHasher::default());
+        let count = SHARD_LOAD as u32 * 4;
+        for key in 0..count {
+            level.insert(key, key);
+        }
+        let shared = level.clone();
+        // the next prefix doubles the 4 shards of the level
+        level.insert(count, count);
+        let shards = |level: &SharedLevel<u32, BuildIntegerHasher>| level.chunks.iter().map(|chunk| chunk.len()).sum::<usize>();
+        let found = (0..=count).all(|key| level.get(&key) == Some(&key));
+        if shards(&level) == 8 && shards(&shared) == 4 && found && shared.len() as u32 == count && shared.get(&count).is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Growing a shared level changes the level sharing it"))
+        }
+    }
+
+    #[test]
+    fn shared_level_copies_one_shard() -> Result<(), String> {
+        let mut level: SharedLevel<u32, BuildIntegerHasher> = SharedLevel::with_hasher(BuildIntegerHasher::default());
+        for key in 0..10_000 {
+            level.insert(key, key);
+        }
+        let shared = level.clone();
+        level.insert(10_000, 10_000);
+        let slots = |level: &SharedLevel<u32, BuildIntegerHasher>| {
+            let shards = level.chunks.iter().flat_map(|chunk| chunk.iter());
+            shards.map(|shard| Arc::clone(&shard.slots)).collect::<Vec<_>>()
+        };
+        let copied = slots(&level).iter().zip(slots(&shared)).filter(|(slots, shared)| !Arc::ptr_eq(slots, shared)).count();
+        if copied == 1 && shared.len() == 10_000 && shared.get(&10_000).is_none() && level.get(&10_000) == Some(&10_000)
+            && level.get(&0) == Some(&0) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Write to a shared level is not isolated to its shard"))
+        }
+    }
+}
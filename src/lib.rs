@@ -4,98 +4,134 @@
 //!
 //! Currently this version of the crate is using the nightly release of rust.
 
-use std::collections::HashMap;
-use std::ptr::NonNull;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::vec;
+
+mod algebra;
+mod arena;
+mod concurrent;
+mod entry;
+mod error;
+mod hasher;
+mod key;
+mod level;
+mod set;
+mod snapshot;
+mod sharded;
+mod yfast;
+
+pub use algebra::{Difference, Intersection, SymmetricDifference, Union};
+pub use arena::{Arena, SharedArena};
+pub use concurrent::{ConcurrentXfast, XfastReadGuard};
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use error::XfastError;
+pub use hasher::{BuildIntegerHasher, IntegerHasher};
+pub use key::XfastKey;
+pub use set::{XfastSet, XfastSetIter, XfastSetRange};
+pub use sharded::{ShardedXfast, ShardedXfastIter};
+pub use snapshot::XfastSnapshot;
+pub use yfast::{Yfast, YfastIter};
+
+use arena::{LeavesMut, NodeId, Storage};
+use level::Level;
 
-type Node<T> = NonNull<TrieNode<T>>;
 /// Nodes of the trie
-#[derive(Debug)]
-pub struct TrieNode<T> {
+#[derive(Debug, Clone)]
+pub struct TrieNode<K, T> {
     // Node key
-    key: usize,
-    /// Node value
-    pub value: Option<T>,
+    key: K,
+    // Node value, only the leaves have a value
+    value: Option<T>,
     // Level at which the node is present
     level: usize,
     // Node's right subtree
-    right: Option<NonNull<TrieNode<T>>>,
+    right: Option<NodeId>,
     // Node's left subtree
-    left: Option<NonNull<TrieNode<T>>>,
+    left: Option<NodeId>,
     // Node's metadata about descendant node status
     is_desc_left: bool,
     is_desc_right: bool,
+    // Count of leaves in the node's subtree
+    count: usize,
 }
 
-impl<T> TrieNode<T> {
+impl<K: XfastKey, T> TrieNode<K, T> {
     
     /// Creates a new node of the trie with `key` and `value` and no children.
-    pub fn new(key: usize, value: T, level: usize) -> Box<Self> {
-        Box::new(TrieNode{
-            key: key,
+    pub fn new(key: K, value: T, level: usize) -> Box<Self> {
+        Box::new(Self::new_leaf(key, value, level))
+    }
+
+    // constructor for leaves, stored in the arena of the trie without boxing
+    fn new_leaf(key: K, value: T, level: usize) -> Self {
+        TrieNode{
+            key,
             value: Some(value),
-            level: level,
+            level,
             right: None,
             left: None,
             is_desc_right: true,
             is_desc_left: true,
-        })
+            count: 1,
+        }
+    }
+
+    /// Returns the key of the node
+    /// # Examples
+    /// ```
+    ///     use xfast::TrieNode;
+    ///
+    ///     let node = TrieNode::new(11_usize, "eleven", 5);
+    ///     assert_eq!(node.key(), 11);
+    /// ```
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    /// Returns a reference to the value of the node
+    /// # Examples
+    /// ```
+    ///     use xfast::TrieNode;
+    ///
+    ///     let node = TrieNode::new(11_usize, "eleven", 5);
+    ///     assert_eq!(*node.value(), "eleven");
+    /// ```
+    pub fn value(&self) -> &T {
+        self.value.as_ref().expect("leaf nodes always have a value")
+    }
+
+    /// Returns a mutable reference to the value of the node
+    /// # Examples
+    /// ```
+    ///     use xfast::TrieNode;
+    ///
+    ///     let mut node = TrieNode::new(11_usize, "eleven", 5);
+    ///     *node.value_mut() = "XI";
+    ///     assert_eq!(*node.value(), "XI");
+    /// ```
+    pub fn value_mut(&mut self) -> &mut T {
+        self.value.as_mut().expect("leaf nodes always have a value")
     }
 
     // constructor for internal nodes
-    fn new_internal(level: usize) -> Box<Self> {
-        Box::new(TrieNode{
-            key: 0,
+    fn new_internal(level: usize) -> Self {
+        TrieNode{
+            key: K::MIN,
             value: None,
             level,
             right: None,
             left: None,
             is_desc_left: true,
             is_desc_right: true,
-        })
-    }
-
-    // return the rightmost node for @cur_node as parent
-    // @max_level: max possible height of the trie
-    fn get_rightmost_node(max_level: usize, mut cur_node: *mut TrieNode<T>) -> Option<Node<T>> {
-        unsafe {
-            while (*cur_node).level != max_level {
-                match (*cur_node).right {
-                    Some(right_node) => {
-                        cur_node = right_node.as_ptr();
-                    }
-                    None => {
-                        (*cur_node).left.map(|left_node| {
-                            cur_node = left_node.as_ptr();
-                        });
-                    }
-                }
-            }
-            NonNull::new(cur_node as *mut TrieNode<T>)
-        }
-    }
-
-    // return the leftmost node for @cur_node as parent
-    // @max_level: max possible height of the trie
-    fn get_leftmost_node(max_level: usize, mut cur_node: *mut TrieNode<T>) -> Option<Node<T>> {
-        unsafe {
-            while (*cur_node).level != max_level {
-                match (*cur_node).left {
-                    Some(left_node) => {
-                        cur_node = left_node.as_ptr();
-                    }
-                    None => {
-                        (*cur_node).right.map(|right_node| {
-                            cur_node = right_node.as_ptr();
-                        });
-                    }
-                }
-            }
-            NonNull::new(cur_node as *mut TrieNode<T>)
+            count: 0,
         }
     }
 }
-
-#[derive(Debug)]
 /// A bitwise trie to store integers.
 ///
 /// The values in a X-fast trie are stored at the leaves. An internal node is added to the trie
@@ -103,66 +139,304 @@ impl<T> TrieNode<T> {
 ///
 /// Each level of the trie is modelled as a hash map storing the trie nodes at that level.
 ///
-/// The range of integers need to be specified while initializing a trie.
+/// The range of integers need to be specified while initializing a trie. Inserting a key larger than
+/// the range panics, `try_insert` reports it as an `XfastError` instead.
+///
+/// The nodes live in an arena owned by the trie and link to each other by index, so like the maps of the standard
+/// library, a trie can be moved to another thread whenever its keys, values and hasher are `Send`, and shared
+/// between threads whenever they are `Sync`:
+/// ```
+///     use std::cell::RefCell;
+///     use xfast::Xfast;
+///
+///     let mut test_trie: Xfast<usize, RefCell<&str>> = Xfast::new(31);
+///     test_trie.insert_key(11, RefCell::new("eleven"));
+///     let handle = std::thread::spawn(move || test_trie.len());
+///     assert_eq!(handle.join().unwrap(), 1);
+/// ```
+/// A trie storing its nodes in a `SharedArena` shares them with its snapshots instead, so it is `Send` only when its
+/// values are both `Send` and `Sync`. A trie is not `Send` when its values are not:
+/// ```compile_fail
+///     use std::rc::Rc;
+///     use xfast::Xfast;
+///
+///     let mut test_trie: Xfast<usize, Rc<&str>> = Xfast::new(31);
+///     test_trie.insert_key(11, Rc::new("eleven"));
+///     std::thread::spawn(move || test_trie.len());
+/// ```
 /// # Examples
 /// ```
 ///     use xfast::Xfast;
 ///
-///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
 ///     test_trie.insert_key(11, "eleven");
 ///     test_trie.insert_key(1, "one");
 ///     test_trie.insert_key(5, "five");
 ///     assert_eq!(test_trie.len(), 3);
 ///        
 ///     let predecessor_3 = test_trie.find_predecessor(3);
-///     if predecessor_3.is_some() {
-///         let predecessor_value = predecessor_3.unwrap().value.unwrap();
-///         assert_eq!(predecessor_value, "one");
+///     if let Some(predecessor_3) = predecessor_3 {
+///         assert_eq!(predecessor_3.key(), 1);
+///         assert_eq!(*predecessor_3.value(), "one");
 ///     }
 /// ```
-
-pub struct Xfast<T=String> {
+pub struct Xfast<K=usize, T=String, S=RandomState, A=Arena<K, T>> {
     nr_levels: usize,
-    level_maps: Vec<HashMap<usize, NonNull<TrieNode<T>>>>,
+    // largest key the trie accepts
+    max_key: K,
+    // each level maps the prefixes of the keys to the nodes of the arena, a snapshot shares the levels along with
+    // a shared arena
+    level_maps: Vec<Level<K, S>>,
+    arena: A,
+    // first and last leaf of the linked list of leaves, kept as leaves are linked and unlinked so that the
+    // ends of the trie are found without walking down from the root
+    ends: Option<(NodeId, NodeId)>,
+    marker: PhantomData<T>,
 }
 
-impl<T> Xfast<T> {
-    
-    /// Creates a new Xfast Trie to store a given `range` of integers
+impl<K: XfastKey, T> Xfast<K, T> {
+
+    /// Creates a new Xfast Trie to store a given `range` of integers.
+    ///
+    /// The height of the trie is the number of bits needed to represent `range`, so passing the
+    /// largest value of the key type makes the whole key type the universe of the trie.
     /// # Examples
     /// ```
     /// # #![allow(unused_mut)]
     ///     use xfast::Xfast;
     ///     
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     let mut ipv6_trie: Xfast<u128, &str> = Xfast::new(u128::MAX);
+    /// ```
+    pub fn new(range: K) -> Self {
+        Self::with_hasher(range, RandomState::new())
+    }
+
+    /// Creates a new Xfast Trie to store a given `range` of integers from key and value pairs sorted by key.
+    ///
+    /// The trie is built in a single pass over the input without any successor search: each key only creates
+    /// the internal nodes below the level where it parts from the previous key, and the descendant pointers and
+    /// leaf counts of a node are set once no later key can fall in its subtree.
+    ///
+    /// Keys larger than `range`, keys smaller than the key before them and repeated keys are reported as an
+    /// `XfastError`.
+    /// # Examples
+    /// ```
+    ///     use xfast::{Xfast, XfastError};
+    ///
+    ///     let test_trie = Xfast::from_sorted_iter(31_usize, vec![(1, "one"), (5, "five"), (11, "eleven")]).unwrap();
+    ///     assert_eq!(test_trie.len(), 3);
+    ///     assert_eq!(test_trie.find_predecessor(4).map(|node| node.key()), Some(1));
+    ///
+    ///     let unsorted = Xfast::from_sorted_iter(31_usize, vec![(5, "five"), (1, "one")]);
+    ///     assert_eq!(unsorted.err(), Some(XfastError::UnsortedKey { key: 1, previous: 5 }));
+    /// ```
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, T)>>(range: K, iter: I) -> Result<Self, XfastError<K>> {
+        Self::from_sorted_iter_with_hasher(range, iter, RandomState::new())
+    }
+}
+
+impl<K: XfastKey, T> Xfast<K, T, BuildIntegerHasher> {
+    /// Creates a new Xfast Trie to store a given `range` of integers, hashing the prefixes of the keys with
+    /// `IntegerHasher`.
+    ///
+    /// The integer hasher is several times faster than the default hasher of the standard library, but it
+    /// doesn't resist collision attacks, so it is meant for keys coming from a trusted source.
+    /// # Examples
+    /// ```
+    ///     use xfast::{BuildIntegerHasher, Xfast};
+    ///
+    ///     let mut test_trie: Xfast<u64, &str, BuildIntegerHasher> = Xfast::with_integer_hasher(u64::MAX);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1 << 40, "two to the forty");
+    ///     assert_eq!(test_trie.find_successor(12).map(|node| node.key()), Some(1 << 40));
+    /// ```
+    pub fn with_integer_hasher(range: K) -> Self {
+        Self::with_hasher(range, BuildIntegerHasher::default())
+    }
+}
+
+impl<K: XfastKey, T, S: BuildHasher + Clone> Xfast<K, T, S> {
+    /// Creates a new Xfast Trie to store a given `range` of integers, hashing the prefixes of the keys at every
+    /// level of the trie with `hasher`.
+    /// # Examples
+    /// ```
+    ///     use std::collections::hash_map::RandomState;
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::with_hasher(31, RandomState::new());
+    ///     test_trie.insert_key(11, "eleven");
+    ///     assert_eq!(test_trie.len(), 1);
+    /// ```
+    pub fn with_hasher(range: K, hasher: S) -> Self {
+        Self::with_hasher_in(range, hasher, Arena::new())
+    }
+
+    /// Creates a new Xfast Trie to store a given `range` of integers from key and value pairs sorted by key,
+    /// hashing the prefixes of the keys with `hasher`. See `from_sorted_iter` for the errors.
+    pub fn from_sorted_iter_with_hasher<I: IntoIterator<Item = (K, T)>>(range: K, iter: I, hasher: S)
+        -> Result<Self, XfastError<K>> {
+        Self::from_sorted_iter_with_hasher_in(range, iter, hasher, Arena::new())
+    }
+}
+
+impl<K: XfastKey, T, A: Storage<K, T>> Xfast<K, T, RandomState, A> {
+    /// Creates a new Xfast Trie to store a given `range` of integers, storing its nodes in `arena`.
+    ///
+    /// A trie stores its nodes in an `Arena` of its own by default. A trie storing its nodes in a `SharedArena`
+    /// can take snapshots sharing the nodes, see `snapshot`.
+    /// # Examples
+    /// ```
+    ///     use xfast::{SharedArena, Xfast};
+    ///
+    ///     let mut test_trie = Xfast::new_in(31_usize, SharedArena::new());
+    ///     test_trie.insert_key(11, "eleven");
+    ///     assert_eq!(test_trie.snapshot().len(), 1);
     /// ```
-    pub fn new(range: usize) -> Self {
+    pub fn new_in(range: K, arena: A) -> Self {
+        Self::with_hasher_in(range, RandomState::new(), arena)
+    }
+}
+
+impl<K: XfastKey, T, S: BuildHasher + Clone, A: Storage<K, T>> Xfast<K, T, S, A> {
+    /// Creates a new Xfast Trie to store a given `range` of integers, hashing the prefixes of the keys with
+    /// `hasher` and storing its nodes in `arena`.
+    pub fn with_hasher_in(range: K, hasher: S, arena: A) -> Self {
         let nr_levels = Self::get_levels_count(range);
-        let level_maps = Self::create_map_list(nr_levels+1);
+        let level_maps = Self::create_map_list(nr_levels+1, &hasher);
         let mut new_trie = Xfast {
             nr_levels,
+            max_key: range,
             level_maps,
+            arena,
+            ends: None,
+            marker: PhantomData,
         };
         // insert the root node in the trie at level 0
-        let root_node = TrieNode::new_internal(0);
-        let root_node = NonNull::from(Box::leak(root_node));
-        new_trie.level_maps[0].insert(0, root_node);
+        let root_node = new_trie.arena.insert(TrieNode::new_internal(0));
+        new_trie.level_maps[0].insert(K::MIN, root_node);
         new_trie
     }
 
-    // levels => height of the trie
-    fn get_levels_count(mut range: usize) -> usize {
-        let mut levels = 0;
-        while range > 0 {
-            range >>= 1;
-            levels += 1;
+    /// Creates a new Xfast Trie to store a given `range` of integers from key and value pairs sorted by key,
+    /// hashing the prefixes of the keys with `hasher` and storing its nodes in `arena`. See `from_sorted_iter`
+    /// for the errors.
+    pub fn from_sorted_iter_with_hasher_in<I: IntoIterator<Item = (K, T)>>(range: K, iter: I, hasher: S, arena: A)
+        -> Result<Self, XfastError<K>> {
+        let mut new_trie = Self::with_hasher_in(range, hasher, arena);
+        let nr_levels = new_trie.nr_levels;
+        let root_node = new_trie.level_maps[0][&K::MIN];
+        // nodes on the path down the last leaf with the first leaf of their subtree and the count of leaves
+        // before that first leaf, the root node gets its first leaf from the first key
+        let mut path = vec![(root_node, None, 0)];
+        let mut last_leaf: Option<NodeId> = None;
+        let mut nr_leaves = 0;
+
+        for (key, value) in iter {
+            new_trie.check_key(key)?;
+            // the highest level at which the key parts from the previous key
+            let mut level = nr_levels;
+            if let Some(last_node) = last_leaf {
+                let previous = new_trie.arena[last_node].key;
+                if key < previous {
+                    return Err(XfastError::UnsortedKey { key, previous });
+                }
+                if key == previous {
+                    return Err(XfastError::DuplicateKey { key });
+                }
+                while level > 1 && key.prefix(nr_levels - level + 1) != previous.prefix(nr_levels - level + 1) {
+                    level -= 1;
+                }
+                // no later key falls in the subtrees of the nodes left on the path below that level
+                for (node, first_leaf, leaves_before) in path.drain(level..) {
+                    if let Some(first_leaf) = first_leaf {
+                        new_trie.finish_sorted_node(node, first_leaf, last_node, nr_leaves - leaves_before);
+                    }
+                }
+            }
+            else {
+                level = 1;
+            }
+
+            let new_node = new_trie.arena.insert(TrieNode::new_leaf(key, value, nr_levels));
+            new_trie.arena[new_node].left = last_leaf;
+            if let Some(last_node) = last_leaf {
+                new_trie.arena[last_node].right = Some(new_node);
+            }
+            if path[0].1.is_none() {
+                path[0].1 = Some(new_node);
+            }
+
+            // create the internal nodes down to the new leaf and link each of them to its parent
+            while level <= nr_levels {
+                let child_node = if level == nr_levels {
+                    new_node
+                }
+                else {
+                    new_trie.arena.insert(TrieNode::new_internal(level))
+                };
+                let prefix = key.prefix(nr_levels - level);
+                let parent_node = &mut new_trie.arena[path[level - 1].0];
+                if prefix.bit(0) {
+                    parent_node.right = Some(child_node);
+                    parent_node.is_desc_right = false;
+                }
+                else {
+                    parent_node.left = Some(child_node);
+                    parent_node.is_desc_left = false;
+                }
+                new_trie.level_maps[level].insert(prefix, child_node);
+                if level < nr_levels {
+                    path.push((child_node, Some(new_node), nr_leaves));
+                }
+                level += 1;
+            }
+
+            last_leaf = Some(new_node);
+            nr_leaves += 1;
+        }
+
+        if let Some(last_node) = last_leaf {
+            new_trie.ends = path[0].1.map(|first_node| (first_node, last_node));
+            for (node, first_leaf, leaves_before) in path.drain(..) {
+                if let Some(first_leaf) = first_leaf {
+                    new_trie.finish_sorted_node(node, first_leaf, last_node, nr_leaves - leaves_before);
+                }
+            }
+        }
+        Ok(new_trie)
+    }
+
+    // sets the leaf count of an internal node built by `from_sorted_iter` and points its missing children
+    // to the first and the last leaf of its subtree
+    fn finish_sorted_node(&mut self, node: NodeId, first_leaf: NodeId, last_leaf: NodeId, count: usize) {
+        let node = &mut self.arena[node];
+        node.count = count;
+        if node.left.is_none() {
+            node.left = Some(first_leaf);
+            node.is_desc_left = true;
+        }
+        if node.right.is_none() {
+            node.right = Some(last_leaf);
+            node.is_desc_right = true;
         }
-        levels
     }
 
-    // helper fn for populating a vector list of hashmaps
-    fn create_map_list(nr_levels: usize) -> Vec<HashMap<usize, Node<T>>> {
-        (0..nr_levels).map(|_| HashMap::new()).collect()
+    // helper fn for populating a vector list of levels
+    fn create_map_list(nr_levels: usize, hasher: &S) -> Vec<Level<K, S>> {
+        (0..nr_levels).map(|_| Level::with_hasher(hasher.clone(), A::SHARED)).collect()
+    }
+}
+
+impl<K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> Xfast<K, T, S, A> {
+    /// Returns a reference to the hasher of the prefixes of the keys
+    pub fn hasher(&self) -> &S {
+        self.level_maps[0].hasher()
+    }
+
+    // levels => height of the trie, a trie always has at least one level below the root
+    fn get_levels_count(range: K) -> usize {
+        range.bit_length().max(1)
     }
 
     /// Returns the count of values stored in the trie
@@ -170,7 +444,7 @@ impl<T> Xfast<T> {
     /// ```
     ///     use xfast::Xfast;
     ///
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
     ///     test_trie.insert_key(11, "eleven");
     ///     test_trie.insert_key(1, "one");
     ///     assert_eq!(test_trie.len(), 2);
@@ -179,19 +453,33 @@ impl<T> Xfast<T> {
         self.level_maps[self.nr_levels].len()
     }
 
-    fn find_lowest_common_ancestor(&self, key: usize) -> Option<*mut TrieNode<T>> {
+    /// Returns true if the trie stores no values
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     assert!(test_trie.is_empty());
+    ///     test_trie.insert_key(11, "eleven");
+    ///     assert!(!test_trie.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn find_lowest_common_ancestor(&self, key: K) -> Option<NodeId> {
         let mut low = 0;
         let mut high = self.nr_levels;
-        let mut ancestor_node: Option<*mut TrieNode<T>> = None;
+        let mut ancestor_node: Option<NodeId> = None;
 
         while high >= low {
             let mid = (low + high)/2;
-            let prefix = key >> (self.nr_levels - mid);
+            let prefix = key.prefix(self.nr_levels - mid);
             //check the presence of an internal node with the keyed as `prefix` in hashmap at the `mid` level 
             match self.level_maps[mid].get(&prefix) {
                 Some(&value) => {
                     low = mid + 1;
-                    ancestor_node = Some(value.as_ptr());
+                    ancestor_node = Some(value);
                 }
                 None => {
                     // prevent out of bound subtraction of a usize
@@ -205,373 +493,544 @@ impl<T> Xfast<T> {
         ancestor_node
     }
 
+    // successor of the `key` as a node of the arena
+    fn find_successor_id(&self, key: K) -> Option<NodeId> {
+        // every stored key is smaller than a key above the range
+        if key > self.max_key {
+            return None;
+        }
+        // find the lowest common ancestor- a node which shares maximum common prefix with the key
+        let node_id = self.find_lowest_common_ancestor(key)?;
+        let node = &self.arena[node_id];
+        // successor of a key already present is the key itself
+        if node.level == self.nr_levels {
+            return Some(node_id);
+        }
+
+        //right subtree of an internal node can have the successor
+        let updated_node = if key.bit(self.nr_levels - node.level - 1) {
+            node.right
+        }
+        else {
+            //left subtree of the internal node has the successor
+            node.left
+        };
+
+        // in case the key of the successor node (leaf node) above calculated has lower key than the currently searched key
+        // navigate using the right and left pointer of the leaf node to find the smallest node which has a key >= the key being searched
+        match updated_node {
+            Some(leaf) if self.arena[leaf].key < key => self.arena[leaf].right,
+            leaf => leaf,
+        }
+    }
+
+    // predecessor of the `key` as a node of the arena
+    fn find_predecessor_id(&self, key: K) -> Option<NodeId> {
+        // the prefixes of a key above the range match no node, its predecessor is the one of the largest key
+        let key = key.min(self.max_key);
+        // find the lowest common ancestor- a node which shares maximum common prefix with the key
+        let node_id = self.find_lowest_common_ancestor(key)?;
+        let node = &self.arena[node_id];
+        // predecessor of a key already present is the key itself
+        if node.level == self.nr_levels {
+            return Some(node_id);
+        }
+
+        let updated_node = if key.bit(self.nr_levels - node.level - 1) {
+            node.right
+        }
+        else {
+            node.left
+        };
+
+        match updated_node {
+            Some(leaf) if self.arena[leaf].key > key => self.arena[leaf].left,
+            leaf => leaf,
+        }
+    }
+
+    // strict successor of the `key` as a node of the arena
+    fn find_strict_successor_id(&self, key: K) -> Option<NodeId> {
+        let node = self.find_successor_id(key)?;
+        // the leaf of the key itself links to the next larger leaf
+        if self.arena[node].key == key {
+            return self.arena[node].right;
+        }
+        Some(node)
+    }
+
+    // strict predecessor of the `key` as a node of the arena
+    fn find_strict_predecessor_id(&self, key: K) -> Option<NodeId> {
+        let node = self.find_predecessor_id(key)?;
+        // the leaf of the key itself links to the next smaller leaf
+        if self.arena[node].key == key {
+            return self.arena[node].left;
+        }
+        Some(node)
+    }
+
     /// Returns the smallest node more than or eqaul to the node associated with `key`. In case of no such node it returns None.
-    /// #Examples
     /// # Examples
     /// ```
     ///     use xfast::Xfast;
     ///
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
     ///     test_trie.insert_key(11, "eleven");
     ///     test_trie.insert_key(1, "one");
     ///     test_trie.insert_key(5, "five");
     ///     assert_eq!(test_trie.len(), 3);
     ///     
     ///     if let Some(successor_3) = test_trie.find_successor(3) {
-    ///         let successor_value = successor_3.value.unwrap();
-    ///         assert_eq!(successor_value, "five");
+    ///         assert_eq!(successor_3.key(), 5);
+    ///         assert_eq!(*successor_3.value(), "five");
     ///     }
     ///     let successor_14 = test_trie.find_successor(14);
     ///         assert!(successor_14.is_none());
     /// ```
-    pub fn find_successor(&self, key: usize) -> Option<&TrieNode<T>> {
-        // find the lowest common ancestor- a node which shares maximum common prefix with the key
-        let successor_node: Option<*mut TrieNode<T>> = self.find_lowest_common_ancestor(key);
-        if let Some(node) = successor_node {
-            unsafe {
-                // successor of a key already present is the key itself
-                if (*node).level == (self.nr_levels) {
-                    return Some(&(*node));
-                }
-
-                //right subtree of an internal node can have the successor
-                let mut updated_node = None;
-                if (key >> (self.nr_levels - (*node).level -1 ) & 1) != 0 {
-                    (*node).right.map(|right_node| {
-                        updated_node = Some(right_node.as_ptr());
-                    });
-                }
-                else {
-                    //left subtree of the internal node has the successor
-                    (*node).left.map(|left_node| {
-                        updated_node = Some(left_node.as_ptr());
-                    });
-                }
-                                
-                // in case the key of the successor node (leaf node) above calculated has lower key than the currently searched key
-                // navigate using the right and left pointer of the leaf node to find the smallest node which has a key >= the key being searched
-                if !updated_node.is_none() && (*updated_node.unwrap()).key < key {
-                    let mut temp_node = None;
-                    (*updated_node.unwrap()).right.map(|right_node| {
-                        temp_node = Some(&(*right_node.as_ptr()));
-                    });
-                    return temp_node;
-                }
-                if !updated_node.is_none() {
-                    return Some(&(*updated_node.unwrap()));
-                }
-                return None;
-            }
-        }
-        None
+    pub fn find_successor(&self, key: K) -> Option<&TrieNode<K, T>> {
+        self.find_successor_id(key).map(|node| &self.arena[node])
     }
 
     /// Returns the largest node less that or eqaul to the node with `key`. In case of no such node it returns None.
-    /// #Examples
+    ///
+    /// A `key` larger than the range of the trie has the largest stored key as predecessor.
     /// # Examples
     /// ```
     ///     use xfast::Xfast;
     ///
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
     ///     test_trie.insert_key(11, "eleven");
     ///     test_trie.insert_key(1, "one");
     ///     test_trie.insert_key(5, "five");
     ///     assert_eq!(test_trie.len(), 3);
     ///        
     ///     if let Some(predecessor_3) = test_trie.find_predecessor(3) {
-    ///         let predecessor_value = predecessor_3.value.unwrap();
-    ///         assert_eq!(predecessor_value, "one");
+    ///         assert_eq!(predecessor_3.key(), 1);
+    ///         assert_eq!(*predecessor_3.value(), "one");
     ///     }
     ///
     ///     let predecessor_0 = test_trie.find_predecessor(0);
     ///         assert!(predecessor_0.is_none());
     /// ```
-    pub fn find_predecessor(&self, key: usize) -> Option<&TrieNode<T>> {
-        // find the lowest common ancestor- a node which shares maximum common prefix with the key
-        let predecessor_node: Option<*mut TrieNode<T>> = self.find_lowest_common_ancestor(key);
-        if let Some(node) = predecessor_node {
-            unsafe {
-                // predecessor of a key already present is the key itself
-                if (*node).level == (self.nr_levels) {
-                    return Some(&(*node));
-                }
+    pub fn find_predecessor(&self, key: K) -> Option<&TrieNode<K, T>> {
+        self.find_predecessor_id(key).map(|node| &self.arena[node])
+    }
 
-                let mut updated_node = None;
-                if (key >> (self.nr_levels - (*node).level -1) & 1) != 0 {
-                    (*node).right.map(|right_node| {
-                        updated_node = Some(right_node.as_ptr());
-                    });
-                }
-                else {
-                    (*node).left.map(|left_node| {
-                        updated_node = Some(left_node.as_ptr());
-                    });
-                }
+    /// Returns the smallest node strictly more than `key`. In case of no such node it returns None.
+    ///
+    /// When `key` is present in the trie its leaf is linked to the strict successor, so no second search is needed.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<u8, &str> = Xfast::new(u8::MAX);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(5, "five");
+    ///     test_trie.insert_key(u8::MAX, "max");
+    ///
+    ///     assert_eq!(test_trie.find_strict_successor(5).unwrap().key(), 11);
+    ///     assert_eq!(test_trie.find_strict_successor(6).unwrap().key(), 11);
+    ///     assert!(test_trie.find_strict_successor(u8::MAX).is_none());
+    /// ```
+    pub fn find_strict_successor(&self, key: K) -> Option<&TrieNode<K, T>> {
+        self.find_strict_successor_id(key).map(|node| &self.arena[node])
+    }
 
-                if !updated_node.is_none() && (*updated_node.unwrap()).key > key {
-                    let mut temp_node = None;
-                    (*updated_node.unwrap()).left.map(|left_node| {
-                        temp_node = Some(&(*left_node.as_ptr()));
-                    });
-                    return temp_node;
-                }
-                if !updated_node.is_none() {
-                return Some(&(*updated_node.unwrap()));
-                }
-                return None;
-                }
-            }
-        None
+    /// Returns the largest node strictly less than `key`. In case of no such node it returns None.
+    /// A `key` larger than the range of the trie has the largest stored key as strict predecessor.
+    ///
+    /// When `key` is present in the trie its leaf is linked to the strict predecessor, so no second search is needed.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<u8, &str> = Xfast::new(u8::MAX);
+    ///     test_trie.insert_key(0, "zero");
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(5, "five");
+    ///
+    ///     assert_eq!(test_trie.find_strict_predecessor(11).unwrap().key(), 5);
+    ///     assert_eq!(test_trie.find_strict_predecessor(10).unwrap().key(), 5);
+    ///     assert!(test_trie.find_strict_predecessor(0).is_none());
+    /// ```
+    pub fn find_strict_predecessor(&self, key: K) -> Option<&TrieNode<K, T>> {
+        self.find_strict_predecessor_id(key).map(|node| &self.arena[node])
+    }
+
+    /// Checked version of `find_successor` which reports keys outside the range of the trie as an error.
+    /// # Examples
+    /// ```
+    ///     use xfast::{Xfast, XfastError};
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     assert_eq!(test_trie.try_find_successor(3).unwrap().unwrap().key(), 11);
+    ///     assert_eq!(test_trie.try_find_successor(32).unwrap_err(), XfastError::KeyOutOfRange { key: 32, max: 31 });
+    /// ```
+    pub fn try_find_successor(&self, key: K) -> Result<Option<&TrieNode<K, T>>, XfastError<K>> {
+        self.check_key(key)?;
+        Ok(self.find_successor(key))
+    }
+
+    /// Checked version of `find_predecessor` which reports keys outside the range of the trie as an error.
+    /// # Examples
+    /// ```
+    ///     use xfast::{Xfast, XfastError};
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     assert_eq!(test_trie.try_find_predecessor(13).unwrap().unwrap().key(), 11);
+    ///     assert_eq!(test_trie.try_find_predecessor(32).unwrap_err(), XfastError::KeyOutOfRange { key: 32, max: 31 });
+    /// ```
+    pub fn try_find_predecessor(&self, key: K) -> Result<Option<&TrieNode<K, T>>, XfastError<K>> {
+        self.check_key(key)?;
+        Ok(self.find_predecessor(key))
+    }
+
+    // keys larger than the range of the trie would collide with other keys on their prefixes
+    fn check_key(&self, key: K) -> Result<(), XfastError<K>> {
+        if key > self.max_key {
+            return Err(XfastError::KeyOutOfRange {
+                key,
+                max: self.max_key,
+            });
+        }
+        Ok(())
     }
 
-    fn populate_internal_nodes(&mut self, key: usize) {
+    fn populate_internal_nodes(&mut self, key: K) {
         let mut level = 1;
         let max_levels = self.nr_levels;
+        // every node on the path down the new leaf gets one more leaf in its subtree
+        if let Some(&root_node) = self.level_maps[0].get(&K::MIN) {
+            self.arena[root_node].count += 1;
+        }
         while level < max_levels {
-            let prefix = key >> (max_levels - level);
-            if let None = self.level_maps[level].get(&prefix) {
-                let temp_node = TrieNode::new_internal(level);
-                let temp_node = NonNull::from(Box::leak(temp_node));
-                self.level_maps[level].insert(prefix, temp_node);
-                // add to the right child if the bit is 1 at that index else make it the left child
-                if (prefix & 1) != 0 {
-                    let temp_prefix = prefix >> 1;
-                    self.level_maps[level-1].get(&temp_prefix).map(|&value| unsafe{
-                        (*value.as_ptr()).right = Some(temp_node);
-                        (*value.as_ptr()).is_desc_right = false;
-                    });
-                }
-                else {
-                    let temp_prefix = prefix >> 1;
-                    self.level_maps[level-1].get(&temp_prefix).map(|&value| unsafe{
-                        (*value.as_ptr()).left = Some(temp_node);
-                        (*value.as_ptr()).is_desc_left = false;
-                    }); 
+            let prefix = key.prefix(max_levels - level);
+            let internal_node = match self.level_maps[level].get(&prefix) {
+                Some(&internal_node) => internal_node,
+                None => {
+                    let temp_node = self.arena.insert(TrieNode::new_internal(level));
+                    self.level_maps[level].insert(prefix, temp_node);
+                    // add to the right child if the bit is 1 at that index else make it the left child
+                    let temp_prefix = prefix.prefix(1);
+                    if let Some(&parent_node) = self.level_maps[level-1].get(&temp_prefix) {
+                        let parent_node = &mut self.arena[parent_node];
+                        if prefix.bit(0) {
+                            parent_node.right = Some(temp_node);
+                            parent_node.is_desc_right = false;
+                        }
+                        else {
+                            parent_node.left = Some(temp_node);
+                            parent_node.is_desc_left = false;
+                        }
+                    }
+                    temp_node
                 }
-            }
+            };
+            self.arena[internal_node].count += 1;
             level += 1;
         }
     }
 
-    fn update_descendant_ptr(&mut self, key: usize) {
+    // return the rightmost leaf below @node
+    fn rightmost_leaf(&self, mut node: NodeId) -> NodeId {
+        while self.arena[node].level != self.nr_levels {
+            let cur_node = &self.arena[node];
+            match cur_node.right.or(cur_node.left) {
+                Some(child_node) => node = child_node,
+                None => break,
+            }
+        }
+        node
+    }
+
+    // return the leftmost leaf below @node
+    fn leftmost_leaf(&self, mut node: NodeId) -> NodeId {
+        while self.arena[node].level != self.nr_levels {
+            let cur_node = &self.arena[node];
+            match cur_node.left.or(cur_node.right) {
+                Some(child_node) => node = child_node,
+                None => break,
+            }
+        }
+        node
+    }
+
+    fn update_descendant_ptr(&mut self, key: K) {
         let mut prefix = key;
         let mut level = self.nr_levels - 1;
 
         while level > 0 {
-            prefix = prefix >> 1;
+            prefix = prefix.prefix(1);
             // find an internal node prefixed as `prefix` at `level` in the level_map
-            self.level_maps[level].get(&prefix).map(|&value| unsafe {
+            if let Some(&value) = self.level_maps[level].get(&prefix) {
+                let node = &self.arena[value];
                 //check if this node has a left child
-                match (*value.as_ptr()).left {
+                match (node.left, node.right) {
                     //the internal node doesn't have a left child
-                    None => {
-                        //An internal node is inserted in a trie only when it has one its children
-                        //Therefore, this node has a right child which is used to find its descendant ptr
-                        (*value.as_ptr()).right.map(|right_node| {
-                            (*value.as_ptr()).left = TrieNode::get_leftmost_node(self.nr_levels, right_node.as_ptr());
-                            (*value.as_ptr()).is_desc_left = true;
-                        });
-                    },
-                    // Left child is present
-                    Some(left_ptr) => {
-                        //this internal node can have a right child or not
-                        match (*value.as_ptr()).right {
-                            // the right child is not present
-                            None => {
-                                (*value.as_ptr()).right = TrieNode::get_rightmost_node(self.nr_levels, left_ptr.as_ptr());
-                                (*value.as_ptr()).is_desc_right = true;
-                            }
-                            // right child is also present
-                            Some(right_ptr)=> {
-                                // if any of the left or the right child is associated with a descendant pointer then update with the latest descendant pointer. 
-                                // At any given instance only one descendant ptr can be present
-                                if (*value.as_ptr()).is_desc_right {
-                                    (*value.as_ptr()).right = TrieNode::get_rightmost_node(self.nr_levels, left_ptr.as_ptr());
-                                }
-                                else if (*value.as_ptr()).is_desc_left {
-                                    (*value.as_ptr()).left = TrieNode::get_leftmost_node(self.nr_levels, right_ptr.as_ptr());
-                                }
-                            }
+                    //An internal node is inserted in a trie only when it has one its children
+                    //Therefore, this node has a right child which is used to find its descendant ptr
+                    (None, Some(right_node)) => {
+                        let leftmost = self.leftmost_leaf(right_node);
+                        let node = &mut self.arena[value];
+                        node.left = Some(leftmost);
+                        node.is_desc_left = true;
+                    }
+                    // the left child is present, the right child is not
+                    (Some(left_node), None) => {
+                        let rightmost = self.rightmost_leaf(left_node);
+                        let node = &mut self.arena[value];
+                        node.right = Some(rightmost);
+                        node.is_desc_right = true;
+                    }
+                    // right child is also present
+                    (Some(left_node), Some(right_node)) => {
+                        // if any of the left or the right child is associated with a descendant pointer then update with the latest descendant pointer. 
+                        // At any given instance only one descendant ptr can be present
+                        if node.is_desc_right {
+                            let rightmost = self.rightmost_leaf(left_node);
+                            self.arena[value].right = Some(rightmost);
+                        }
+                        else if node.is_desc_left {
+                            let leftmost = self.leftmost_leaf(right_node);
+                            self.arena[value].left = Some(leftmost);
                         }
                     }
+                    (None, None) => {}
                 }
-            });
+            }
             level -= 1;
         }
 
         // update the descendant ptr for the root node
-        self.level_maps[0].get(&0).map(|&value| unsafe {
-            let is_left_descendant = (*value.as_ptr()).is_desc_left;
-            let is_right_descendant = (*value.as_ptr()).is_desc_right;
-            if is_left_descendant {
-                (*value.as_ptr()).right.map(|right_node| {
-                    (*value.as_ptr()).left = TrieNode::get_leftmost_node(self.nr_levels, right_node.as_ptr());
-                });
-            }
-            if is_right_descendant {
-                (*value.as_ptr()).left.map(|left_node| {
-                    (*value.as_ptr()).right = TrieNode::get_rightmost_node(self.nr_levels, left_node.as_ptr());
-                });
+        if let Some(&value) = self.level_maps[0].get(&K::MIN) {
+            let node = &self.arena[value];
+            let is_left_descendant = node.is_desc_left;
+            let is_right_descendant = node.is_desc_right;
+            if is_left_descendant && is_right_descendant {
+                // the trie is empty, drop the stale descendant ptrs to the last deleted leaf
+                let node = &mut self.arena[value];
+                node.left = None;
+                node.right = None;
             }
-        });
+            else if is_left_descendant {
+                if let Some(right_node) = node.right {
+                    let leftmost = self.leftmost_leaf(right_node);
+                    self.arena[value].left = Some(leftmost);
+                }
+            }
+            else if is_right_descendant {
+                if let Some(left_node) = node.left {
+                    let rightmost = self.rightmost_leaf(left_node);
+                    self.arena[value].right = Some(rightmost);
+                }
+            }
+        }
     }
 
-    /// Insert `key` and `value` into the trie
+    /// Insert `key` and `value` into the trie.
+    ///
+    /// If the trie already has the `key`, its value is replaced and the old value is returned,
+    /// otherwise None is returned.
+    ///
+    /// # Panics
+    /// Panics if `key` is larger than the range of the trie.
     /// # Examples
     /// ```
     ///     use xfast::Xfast;
     ///
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
-    ///     test_trie.insert_key(11, "eleven");
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     assert_eq!(test_trie.insert_key(11, "eleven"), None);
+    ///     assert_eq!(test_trie.insert_key(11, "XI"), Some("eleven"));
+    ///     assert_eq!(test_trie.len(), 1);
     /// ```
-    pub fn insert_key(&mut self, key: usize, value: T) {
-        //create a new node with key and val
-        let new_node = TrieNode::new(key, value, self.nr_levels);
-        let new_node = Some(NonNull::from(Box::leak(new_node)));
-        //find predecessor and successor for the new node
-        let predecessor = self.find_predecessor(key);
-        let successor = self.find_successor(key);
-        
-        //update the right and left pointers of the new node to refer to its successors and predecessors resp.
-        //update the right ptr in the predecessor ,and left ptr in the successor with the new_node.
-        predecessor.map(|pred_node| unsafe{
-            //FIXME
-            let pred_node = &(*pred_node) as *const TrieNode<T> as *mut TrieNode<T>;
-            new_node.map(|node| {
-                (*node.as_ptr()).right = (*pred_node).right;
-                (*node.as_ptr()).left = NonNull::new(pred_node);
-            });
-            (*pred_node).right = new_node;
-        });
+    pub fn insert_key(&mut self, key: K, value: T) -> Option<T> {
+        match self.try_insert(key, value) {
+            Ok(old_value) => old_value,
+            Err(err) => panic!("{}", err),
+        }
+    }
 
-        successor.map(|suc_node| unsafe{
-            let suc_node = &(*suc_node) as *const TrieNode<T> as *mut TrieNode<T>;
-            new_node.map(|node| {
-                (*node.as_ptr()).left = (*suc_node).left;
-                (*node.as_ptr()).right = NonNull::new(suc_node);
-            });
-            (*suc_node).left = new_node;
-        });
+    /// Insert `key` and `value` into the trie, reporting keys larger than the range of the trie as an error
+    /// instead of panicking.
+    /// # Examples
+    /// ```
+    ///     use xfast::{Xfast, XfastError};
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     assert_eq!(test_trie.try_insert(11, "eleven"), Ok(None));
+    ///     assert_eq!(test_trie.try_insert(11, "XI"), Ok(Some("eleven")));
+    ///     assert_eq!(test_trie.try_insert(32, "thirty two"), Err(XfastError::KeyOutOfRange { key: 32, max: 31 }));
+    ///     assert_eq!(test_trie.len(), 1);
+    /// ```
+    pub fn try_insert(&mut self, key: K, value: T) -> Result<Option<T>, XfastError<K>> {
+        self.check_key(key)?;
+
+        // an existing leaf only gets its value replaced, the structure of the trie stays the same
+        if let Some(node) = self.find_key_id(key) {
+            return Ok(self.arena[node].value.replace(value));
+        }
+
+        self.insert_leaf(key, value);
+        Ok(None)
+    }
+
+    // links a new leaf for a `key` not present in the trie and returns it
+    fn insert_leaf(&mut self, key: K, value: T) -> NodeId {
+        //find successor for the new node, the predecessor is the leaf before the successor in the linked list of leaves
+        let successor = self.find_successor_id(key);
+        let predecessor = match successor {
+            Some(suc_node) => self.arena[suc_node].left,
+            None => self.find_predecessor_id(key),
+        };
+
+        //create a new node with key and val
+        //update the right and left pointers of the new node to refer to its successors and predecessors resp.
+        //update the right ptr in the predecessor ,and left ptr in the successor with the new_node.
+        let mut new_node = TrieNode::new_leaf(key, value, self.nr_levels);
+        new_node.left = predecessor;
+        new_node.right = successor;
+        let new_node = self.arena.insert(new_node);
+        if let Some(pred_node) = predecessor {
+            self.arena[pred_node].right = Some(new_node);
+        }
+        if let Some(suc_node) = successor {
+            self.arena[suc_node].left = Some(new_node);
+        }
+        let (first_node, last_node) = self.ends.unwrap_or((new_node, new_node));
+        self.ends = Some((
+            if predecessor.is_none() { new_node } else { first_node },
+            if successor.is_none() { new_node } else { last_node },
+        ));
 
         //populate intermediate iternal nodes on the path down the new_node
         self.populate_internal_nodes(key);
         
         //insert the new_node at the last level and update the ptr of its parent node using the prefix bit
-        self.level_maps[self.nr_levels].insert(key, new_node.unwrap());
-        let temp_key = key >> 1;
-        self.level_maps[self.nr_levels-1].get(&temp_key).map(|&value| unsafe {
-            if (key & 1) != 0 {
-                (*value.as_ptr()).right= new_node;
-                (*value.as_ptr()).is_desc_right = false;
+        self.level_maps[self.nr_levels].insert(key, new_node);
+        let temp_key = key.prefix(1);
+        if let Some(&value) = self.level_maps[self.nr_levels-1].get(&temp_key) {
+            let parent_node = &mut self.arena[value];
+            if key.bit(0) {
+                parent_node.right = Some(new_node);
+                parent_node.is_desc_right = false;
             }
             else {
-                (*value.as_ptr()).left = new_node;
-                (*value.as_ptr()).is_desc_left = false;
+                parent_node.left = Some(new_node);
+                parent_node.is_desc_left = false;
             }
-        });
+        }
 
         // update descendant ptrs
         self.update_descendant_ptr(key);
+        new_node
     }
 
-    fn delete_internal_node(&mut self, key: usize) {
+    fn delete_internal_node(&mut self, key: K) {
         let mut level = self.nr_levels-1;
         let mut prefix = key;
         let mut child_prefix = key;
 
         while level > 0 {
-            prefix = prefix >> 1;
-            if let Some(internal_node) = self.level_maps[level].get(&prefix) {
-                unsafe {
-                    //check if it has a descendant node
-                    if (child_prefix &1) == 1 {
-                        //check left node
-                        if !(*internal_node.as_ptr()).is_desc_left {
-                            break;
-                        }
-                    }
-                    else if !(*internal_node.as_ptr()).is_desc_right {
-                            break;
+            prefix = prefix.prefix(1);
+            if let Some(&internal_node) = self.level_maps[level].get(&prefix) {
+                //check if it has a descendant node
+                if child_prefix.bit(0) {
+                    //check left node
+                    if !self.arena[internal_node].is_desc_left {
+                        break;
                     }
                 }
+                else if !self.arena[internal_node].is_desc_right {
+                        break;
+                }
             }
                     
-            let parent_prefix = prefix >> 1;
-            self.level_maps[level-1].get(&parent_prefix).map(|parent_node| unsafe{
+            let parent_prefix = prefix.prefix(1);
+            if let Some(&parent_node) = self.level_maps[level-1].get(&parent_prefix) {
+                let parent_node = &mut self.arena[parent_node];
                 //node present in right subtree
-                if (prefix & 1) != 0 {
-                    (*parent_node.as_ptr()).right = None;
-                    (*parent_node.as_ptr()).is_desc_right = true;
+                if prefix.bit(0) {
+                    parent_node.right = None;
+                    parent_node.is_desc_right = true;
                 }
                 else {
-                    (*parent_node.as_ptr()).left = None;
-                    (*parent_node.as_ptr()).is_desc_left = true;
+                    parent_node.left = None;
+                    parent_node.is_desc_left = true;
                 }
-            });
-            self.level_maps[level].remove(&prefix);
-            child_prefix = child_prefix>>1;
+            }
+            // the internal node is unlinked, its slot goes back to the free list of the arena
+            if let Some(internal_node) = self.level_maps[level].remove(&prefix) {
+                self.arena.remove(internal_node);
+            }
+            child_prefix = child_prefix.prefix(1);
             level -= 1;
         }
+
+        // the ancestors left in the trie have one leaf less in their subtrees
+        for level in (0..=level).rev() {
+            if let Some(&internal_node) = self.level_maps[level].get(&key.prefix(self.nr_levels - level)) {
+                self.arena[internal_node].count -= 1;
+            }
+        }
     }
 
-    /// Delete a key from the trie. If the node doesn't exist it returns None else retuns the deleted   `TrieNode` wrapped in a `NonNull` struct.
+    /// Delete a key from the trie. If the node doesn't exist it returns None else returns the value stored with the deleted key.
     /// # Examples
     ///  ```
     ///     use xfast::Xfast;
     ///
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
     ///     test_trie.insert_key(11, "eleven");
     ///     test_trie.insert_key(1, "one");
     ///     test_trie.insert_key(5, "five");
     ///     assert_eq!(test_trie.len(), 3);
     ///        
-    ///     test_trie.delete_key(5);
+    ///     assert_eq!(test_trie.delete_key(5), Some("five"));
     ///     assert_eq!(test_trie.len(), 2);
     ///     assert!(test_trie.delete_key(2).is_none());
     ///     assert_eq!(test_trie.len(), 2);
     /// ```
-    pub fn delete_key(&mut self, key: usize) -> Option<Node<T>>{
+    pub fn delete_key(&mut self, key: K) -> Option<T> {
         //find the key in the lowest level
-        let deleted_node = self.find_key_as_non_null(key);
-        if deleted_node.is_none() {
-            return None;
-        }
-
-        let deleted_node = deleted_node.unwrap();
+        let deleted_node = self.find_key_id(key)?;
         
-        self.level_maps[self.nr_levels-1].get(&(key>>1)).map(|internal_node| unsafe{
-            if (key &1) == 1 {
-                (*internal_node.as_ptr()).right = None;
-                (*internal_node.as_ptr()).is_desc_right = true;
+        if let Some(&internal_node) = self.level_maps[self.nr_levels-1].get(&key.prefix(1)) {
+            let internal_node = &mut self.arena[internal_node];
+            if key.bit(0) {
+                internal_node.right = None;
+                internal_node.is_desc_right = true;
             }
             else {
-                (*internal_node.as_ptr()).left = None;
-                (*internal_node.as_ptr()).is_desc_left = true;
+                internal_node.left = None;
+                internal_node.is_desc_left = true;
             }
-        });
+        }
         
         self.delete_internal_node(key);
-        unsafe {
-            let predecessor_node = (*deleted_node.as_ptr()).left;
-            let successor_node = (*deleted_node.as_ptr()).right;
-            
-            if !predecessor_node.is_none() {
-                (*predecessor_node.unwrap().as_ptr()).right = successor_node;
-            }
-            if !successor_node.is_none() {
-
-                (*successor_node.unwrap().as_ptr()).left = predecessor_node;
-            }
+        let predecessor_node = self.arena[deleted_node].left;
+        let successor_node = self.arena[deleted_node].right;
+        if let Some(predecessor_node) = predecessor_node {
+            self.arena[predecessor_node].right = successor_node;
         }
-        let deleted_node = self.level_maps[self.nr_levels].remove(&key);
+        if let Some(successor_node) = successor_node {
+            self.arena[successor_node].left = predecessor_node;
+        }
+        self.ends = match (predecessor_node, successor_node) {
+            (None, None) => None,
+            (None, Some(successor_node)) => self.ends.map(|(_, last_node)| (successor_node, last_node)),
+            (Some(predecessor_node), None) => self.ends.map(|(first_node, _)| (first_node, predecessor_node)),
+            (Some(_), Some(_)) => self.ends,
+        };
+        self.level_maps[self.nr_levels].remove(&key);
         self.update_descendant_ptr(key);
-        deleted_node
+        // the leaf is unlinked from the trie, free its slot and hand out its value
+        self.arena.remove(deleted_node).value
     }
 
-    fn find_key_as_non_null(&self, key: usize) -> Option<Node<T>> {
-        self.level_maps[self.nr_levels].get(&key).map(|&value| {
-            value
-        })
+    fn find_key_id(&self, key: K) -> Option<NodeId> {
+        self.level_maps[self.nr_levels].get(&key).copied()
     }
 
     /// Find a key in the trie
@@ -579,188 +1038,981 @@ impl<T> Xfast<T> {
     ///  ```
     ///     use xfast::Xfast;
     ///
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
     ///     test_trie.insert_key(11, "eleven");
     ///     test_trie.insert_key(1, "one");
     ///     if let Some(node_1) = test_trie.find_key(1) {
-    ///         // all the leaf nodes values have non trivial values and assert is_some. 
-    ///         // So unwrapping will not panic
-    ///         assert_eq!(node_1.value.unwrap(), "one");
+    ///         assert_eq!(*node_1.value(), "one");
+    ///     }
+    /// ```
+    pub fn find_key(&self, key: K) -> Option<&TrieNode<K, T>> {
+        self.find_key_id(key).map(|node| &self.arena[node])
+    }
+
+    /// Returns a reference to the value stored with `key`. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     assert_eq!(test_trie.get(&11), Some(&"eleven"));
+    ///     assert_eq!(test_trie.get(&12), None);
+    /// ```
+    pub fn get(&self, key: &K) -> Option<&T> {
+        self.find_key(*key).map(|node| node.value())
+    }
+
+    /// Returns a mutable reference to the value stored with `key`. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     if let Some(value) = test_trie.get_mut(&11) {
+    ///         *value = "XI";
+    ///     }
+    ///     assert_eq!(test_trie.get(&11), Some(&"XI"));
+    /// ```
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        let node = self.find_key_id(*key)?;
+        Some(self.arena[node].value_mut())
+    }
+
+    /// Returns true if the trie has a value stored with `key`
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     assert!(test_trie.contains_key(&11));
+    ///     assert!(!test_trie.contains_key(&12));
+    /// ```
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.level_maps[self.nr_levels].contains_key(key)
+    }
+
+    /// Insert `key` and `value` into the trie, returning the value previously stored with `key`.
+    ///
+    /// This is the same as `insert_key` and panics on keys larger than the range of the trie.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     assert_eq!(test_trie.insert(11, "eleven"), None);
+    ///     assert_eq!(test_trie.insert(11, "XI"), Some("eleven"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: T) -> Option<T> {
+        self.insert_key(key, value)
+    }
+
+    /// Removes `key` from the trie and returns its value. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     assert_eq!(test_trie.remove(&11), Some("eleven"));
+    ///     assert_eq!(test_trie.remove(&11), None);
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<T> {
+        self.delete_key(*key)
+    }
+
+    /// Returns the entry of `key` in the trie for an in-place lookup and update.
+    ///
+    /// Only the leaf level is searched to find the entry, the trie is walked again only when a vacant entry is filled.
+    ///
+    /// # Panics
+    /// Panics if `key` is larger than the range of the trie.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, usize> = Xfast::new(31);
+    ///     for key in vec![11, 1, 11, 5, 11] {
+    ///         *test_trie.entry(key).or_insert(0) += 1;
     ///     }
+    ///     assert_eq!(test_trie.get(&11), Some(&3));
+    ///     assert_eq!(test_trie.get(&5), Some(&1));
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, T, S, A> {
+        if let Err(err) = self.check_key(key) {
+            panic!("{}", err);
+        }
+        Entry::new(self, key)
+    }
+
+    /// Checked version of `find_key` which reports keys outside the range of the trie as an error.
+    /// # Examples
+    ///  ```
+    ///     use xfast::{Xfast, XfastError};
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     assert!(test_trie.try_find_key(11).unwrap().is_some());
+    ///     assert!(test_trie.try_find_key(12).unwrap().is_none());
+    ///     assert_eq!(test_trie.try_find_key(40).unwrap_err(), XfastError::KeyOutOfRange { key: 40, max: 31 });
+    /// ```
+    pub fn try_find_key(&self, key: K) -> Result<Option<&TrieNode<K, T>>, XfastError<K>> {
+        self.check_key(key)?;
+        Ok(self.find_key(key))
+    }
+
+    // leaf with the smallest key
+    fn first_leaf(&self) -> Option<NodeId> {
+        self.ends.map(|(first_node, _)| first_node)
+    }
+
+    // leaf with the largest key
+    fn last_leaf(&self) -> Option<NodeId> {
+        self.ends.map(|(_, last_node)| last_node)
+    }
+
+    /// Returns the node with the smallest key in the trie. In case of an empty trie it returns None.
+    ///
+    /// The trie keeps the ends of its linked list of leaves up to date on every insertion and deletion, so this
+    /// takes O(1) time.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     assert!(test_trie.first().is_none());
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     test_trie.insert_key(19, "nineteen");
+    ///     assert_eq!(test_trie.first().unwrap().key(), 1);
+    /// ```
+    pub fn first(&self) -> Option<&TrieNode<K, T>> {
+        self.first_leaf().map(|node| &self.arena[node])
+    }
+
+    /// Returns the node with the largest key in the trie. In case of an empty trie it returns None.
+    ///
+    /// Like `first`, this takes O(1) time.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     assert!(test_trie.last().is_none());
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     test_trie.insert_key(19, "nineteen");
+    ///     assert_eq!(test_trie.last().unwrap().key(), 19);
+    /// ```
+    pub fn last(&self) -> Option<&TrieNode<K, T>> {
+        self.last_leaf().map(|node| &self.arena[node])
+    }
+
+    /// Removes the smallest key from the trie and returns it along with its value. In case of an empty trie it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     assert_eq!(test_trie.pop_first(), Some((1, "one")));
+    ///     assert_eq!(test_trie.pop_first(), Some((11, "eleven")));
+    ///     assert_eq!(test_trie.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<(K, T)> {
+        let key = self.arena[self.first_leaf()?].key;
+        self.delete_key(key).map(|value| (key, value))
+    }
+
+    /// Removes the largest key from the trie and returns it along with its value. In case of an empty trie it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     assert_eq!(test_trie.pop_last(), Some((11, "eleven")));
+    ///     assert_eq!(test_trie.pop_last(), Some((1, "one")));
+    ///     assert_eq!(test_trie.pop_last(), None);
     /// ```
-    pub fn find_key(&self, key: usize) -> Option<&TrieNode<T>> {
-        self.level_maps[self.nr_levels].get(&key).map(|&value| unsafe {
-            &(*value.as_ptr())
-        })
+    pub fn pop_last(&mut self) -> Option<(K, T)> {
+        let key = self.arena[self.last_leaf()?].key;
+        self.delete_key(key).map(|value| (key, value))
     }
 
-    /// Returns an iterator around all the key-TrieNode pairs stored in the trie.
+    /// Returns an iterator around all the key-TrieNode pairs stored in the trie in ascending order of the keys.
+    ///
+    /// The leaves of the trie are linked to their neighbours, so the iterator simply walks the linked list
+    /// of leaves from both of its ends.
     /// # Examples
     /// ```
     ///     use xfast::Xfast;
     ///
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
     ///     test_trie.insert_key(11, "eleven");
     ///     test_trie.insert_key(1, "one");
     ///     test_trie.insert_key(19, "nineteen");
     ///     for (key, node) in test_trie.iter() {
     ///         println!("key: {} value: {:?}", key, node);
     ///     }
+    ///
+    ///     let keys: Vec<usize> = test_trie.iter().map(|(&key, _)| key).collect();
+    ///     assert_eq!(keys, vec![1, 11, 19]);
+    ///     let keys: Vec<usize> = test_trie.iter().rev().map(|(&key, _)| key).collect();
+    ///     assert_eq!(keys, vec![19, 11, 1]);
     /// ```
-    pub fn iter(&self) -> XfastIter<T> {
-        let leaf_map = &self.level_maps[self.nr_levels];
-        let mut keys: Vec<usize> = vec!();
-        
-        for &cur_key in leaf_map.keys() {
-            keys.push(cur_key);
-        }
-
+    pub fn iter(&self) -> XfastIter<'_, K, T, A> {
         XfastIter {
-            leaf_map,
-            keys,
-            index: 0,
+            arena: &self.arena,
+            front: self.first_leaf(),
+            back: self.last_leaf(),
+            remaining: self.len(),
+            marker: PhantomData,
         }
     }
 
-    /// Returns a mutable iterator around all the key-TrieNode pairs stored in the trie.
+    /// Returns a mutable iterator around all the key-TrieNode pairs stored in the trie in ascending order of the keys.
     /// # Examples
     /// ```
     ///     use xfast::Xfast;
     ///
-    ///     let mut test_trie: Xfast<&str> = Xfast::new(31);
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
     ///     test_trie.insert_key(11, "eleven");
     ///     test_trie.insert_key(1, "one");
     ///     test_trie.insert_key(19, "nineteen");
     ///     for (key, node) in test_trie.iter_mut() {
     ///         if key % 2 == 1 {
-    ///             node.value = Some("updated_odd");    
+    ///             *node.value_mut() = "updated_odd";
     ///         }
     ///     }
     ///
     ///     if let Some(node_1) = test_trie.find_key(1) {
-    ///         assert_eq!(node_1.value.unwrap(), "updated_odd");
+    ///         assert_eq!(*node_1.value(), "updated_odd");
     ///     }
     /// ```
-    pub fn iter_mut(&mut self) -> XfastIterMut<T> {
-        let leaf_map = &self.level_maps[self.nr_levels];
-        let mut keys: Vec<usize> = vec!();
-        
-        for &cur_key in leaf_map.keys() {
-            keys.push(cur_key);
-        }
-
+    pub fn iter_mut(&mut self) -> XfastIterMut<'_, K, T, S, A> {
+        let ends = self.first_leaf().zip(self.last_leaf());
+        let remaining = self.len();
         XfastIterMut {
-            leaf_map,
-            keys,
-            index: 0,
+            leaves: LeavesMut::new(&mut self.arena, ends),
+            leaf_map: &self.level_maps[self.nr_levels],
+            remaining,
         }
     }
-}
 
-/// Iterator around the Xfast key and value (TrieNode) pairs
+    // first and last leaves of the leaves with keys within `range`
+    fn range_ends<R: RangeBounds<K>>(&self, range: R) -> Option<(NodeId, NodeId)> {
+        // inverted ranges are rejected the way `BTreeMap::range` rejects them
+        match (range.start_bound(), range.end_bound()) {
+            (Bound::Excluded(start), Bound::Excluded(end)) if start == end => {
+                panic!("range start and end are equal and excluded in Xfast")
+            }
+            (Bound::Included(start) | Bound::Excluded(start), Bound::Included(end) | Bound::Excluded(end))
+                if start > end => panic!("range start is greater than range end in Xfast"),
+            _ => {}
+        }
 
-pub struct XfastIter<'a, T> {
-    leaf_map: &'a HashMap<usize, Node<T>>,
-    keys: Vec<usize>,
-    index: usize,
-}
+        // the smallest leaf inside the range is the successor of the lower bound
+        let front = match range.start_bound() {
+            Bound::Included(&start) => self.find_successor_id(start)?,
+            Bound::Excluded(&start) => self.find_strict_successor_id(start)?,
+            Bound::Unbounded => self.first_leaf()?,
+        };
 
-impl<'a, T> Iterator for XfastIter<'a, T> {
-    type Item = (&'a usize, &'a TrieNode<T>);
+        // the largest leaf inside the range is the predecessor of the upper bound
+        let back = match range.end_bound() {
+            Bound::Included(&end) => self.find_predecessor_id(end)?,
+            Bound::Excluded(&end) => self.find_strict_predecessor_id(end)?,
+            Bound::Unbounded => self.last_leaf()?,
+        };
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.leaf_map.len() {
-            let key = self.keys[self.index];
-            self.index += 1;
-            let kv_pair = self.leaf_map.get_key_value(&key);
-            kv_pair.map(|(key, value)| unsafe{
-                let value = value.as_ref();
-                (key, value)
-            })
-        }
-        else {
-            None
+        if self.arena[front].key > self.arena[back].key {
+            return None;
         }
+        Some((front, back))
     }
-}
-
-/// Mutable Iterator around the Xfast key and value (TrieNode) pairs
-pub struct XfastIterMut<'a, T> {
-    leaf_map: &'a HashMap<usize, Node<T>>,
-    keys: Vec<usize>,
-    index: usize,
-}
-
-impl<'a, T> Iterator for XfastIterMut<'a, T> {
-    type Item = (&'a usize, &'a mut TrieNode<T>);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.leaf_map.len() {
-            let key = self.keys[self.index];
-            self.index += 1;
-            let kv_pair = self.leaf_map.get_key_value(&key);
-            kv_pair.map(|(key, value)| unsafe{
-                let value = &mut (*value.as_ptr());
-                (key, value)
-            })
-        }
-        else {
-            None
+    /// Returns a double ended iterator around the key-TrieNode pairs with keys within `range` in ascending order of the keys.
+    ///
+    /// The iteration starts from the successor of the lower bound and follows the linked list of leaves up to the
+    /// predecessor of the upper bound. An empty range yields no pairs.
+    ///
+    /// # Panics
+    /// Panics like `BTreeMap::range` if the start of `range` is larger than its end, or if both bounds are
+    /// excluded and equal.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     test_trie.insert_key(19, "nineteen");
+    ///     test_trie.insert_key(5, "five");
+    ///
+    ///     let keys: Vec<usize> = test_trie.range(2..19).map(|(&key, _)| key).collect();
+    ///     assert_eq!(keys, vec![5, 11]);
+    ///     let keys: Vec<usize> = test_trie.range(5..).rev().map(|(&key, _)| key).collect();
+    ///     assert_eq!(keys, vec![19, 11, 5]);
+    ///     assert_eq!(test_trie.range(12..=18).count(), 0);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> XfastRange<'_, K, T, A> {
+        let (front, back) = match self.range_ends(range) {
+            Some((front, back)) => (Some(front), Some(back)),
+            None => (None, None),
+        };
+        XfastRange {
+            arena: &self.arena,
+            front,
+            back,
+            marker: PhantomData,
         }
     }
-}
 
-impl<'a, T> IntoIterator for &'a Xfast<T> {
-    type Item = (&'a usize, &'a TrieNode<T>);
-    type IntoIter = XfastIter<'a, T>;
-    fn into_iter(self) -> XfastIter<'a, T> {
-        self.iter()
+    /// Returns a double ended mutable iterator around the key-TrieNode pairs with keys within `range` in ascending order of the keys.
+    ///
+    /// # Panics
+    /// Panics on the same inverted ranges as `range`.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     test_trie.insert_key(19, "nineteen");
+    ///     for (_, node) in test_trie.range_mut(..=11) {
+    ///         *node.value_mut() = "small";
+    ///     }
+    ///
+    ///     assert_eq!(*test_trie.find_key(1).unwrap().value(), "small");
+    ///     assert_eq!(*test_trie.find_key(11).unwrap().value(), "small");
+    ///     assert_eq!(*test_trie.find_key(19).unwrap().value(), "nineteen");
+    /// ```
+    pub fn range_mut<R: RangeBounds<K>>(&mut self, range: R) -> XfastRangeMut<'_, K, T, S, A> {
+        let ends = self.range_ends(range);
+        XfastRangeMut {
+            leaves: LeavesMut::new(&mut self.arena, ends),
+            leaf_map: &self.level_maps[self.nr_levels],
+        }
     }
-}
-
-
 
-mod test{
-    use super::Xfast;
-
-    fn init()  -> Xfast<String> {
-        let mut test_trie: Xfast<String> = Xfast::new(31);
-        test_trie.insert_key(11, String::from("eleven"));
-        test_trie.insert_key(1, String::from("one"));
-        test_trie.insert_key(18, String::from("eighteen"));
-        test_trie.insert_key(5, String::from("five"));
-        test_trie
+    // real child of an internal node, descendant ptrs are not children
+    fn child(&self, node: NodeId, right: bool) -> Option<NodeId> {
+        let node = &self.arena[node];
+        match right {
+            true if !node.is_desc_right => node.right,
+            false if !node.is_desc_left => node.left,
+            _ => None,
+        }
     }
 
-    #[test]
-    fn successor() -> Result<(), String> {
-        let test_trie = init();
-        if let Some(successor) = test_trie.find_successor(7) {
-            if successor.key == 11 {
-                return Ok(())
+    /// Returns the count of keys in the trie strictly less than `key`.
+    ///
+    /// Every internal node keeps the count of leaves in its subtree, so the rank is summed up on the path
+    /// from the root towards `key` in O(log u) time.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     test_trie.insert_key(5, "five");
+    ///     assert_eq!(test_trie.rank(0), 0);
+    ///     assert_eq!(test_trie.rank(5), 1);
+    ///     assert_eq!(test_trie.rank(6), 2);
+    ///     assert_eq!(test_trie.rank(31), 3);
+    /// ```
+    pub fn rank(&self, key: K) -> usize {
+        // keys beyond the height of the trie are larger than every key in it
+        if key.bit_length() > self.nr_levels {
+            return self.len();
+        }
+        let mut rank = 0;
+        let mut node = self.level_maps[0].get(&K::MIN).copied();
+        let mut level = 0;
+        while let Some(cur_node) = node {
+            if level == self.nr_levels {
+                break;
             }
+            if key.bit(self.nr_levels - level - 1) {
+                // the whole left subtree is smaller than the key
+                if let Some(left_node) = self.child(cur_node, false) {
+                    rank += self.arena[left_node].count;
+                }
+                node = self.child(cur_node, true);
+            }
+            else {
+                node = self.child(cur_node, false);
+            }
+            level += 1;
         }
-        Err(String::from("Successor of 7 is wrong"))
+        rank
     }
 
-    #[test]
-    fn none_successor() -> Result<(), String> {
-        let test_trie = init();
-        if test_trie.find_successor(19).is_none() {
-            Ok(())
+    /// Returns the node with the `index`-th smallest key, counting from 0. In case of no such node it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     test_trie.insert_key(5, "five");
+    ///     assert_eq!(test_trie.select(0).unwrap().key(), 1);
+    ///     assert_eq!(test_trie.select(2).unwrap().key(), 11);
+    ///     assert!(test_trie.select(3).is_none());
+    /// ```
+    pub fn select(&self, mut index: usize) -> Option<&TrieNode<K, T>> {
+        if index >= self.len() {
+            return None;
         }
-        else {
-            Err(String::from("Successor of 19 is wrong"))
+        let mut node = *self.level_maps[0].get(&K::MIN)?;
+        for _ in 0..self.nr_levels {
+            let left_count = self.child(node, false).map_or(0, |left_node| self.arena[left_node].count);
+            if index < left_count {
+                node = self.child(node, false)?;
+            }
+            else {
+                index -= left_count;
+                node = self.child(node, true)?;
+            }
         }
+        Some(&self.arena[node])
     }
 
-    #[test]
-    fn predecessor() -> Result<(), String> {
-        let test_trie = init();
-        if let Some(predecessor) = test_trie.find_predecessor(8) {
-            if predecessor.key == 5 {
-                return Ok(())
-            }
+    /// Returns the count of keys in the trie within `range`
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     test_trie.insert_key(5, "five");
+    ///     assert_eq!(test_trie.count_range(1..11), 2);
+    ///     assert_eq!(test_trie.count_range(1..=11), 3);
+    ///     assert_eq!(test_trie.count_range(6..), 1);
+    /// ```
+    pub fn count_range<R: RangeBounds<K>>(&self, range: R) -> usize {
+        let lower = match range.start_bound() {
+            Bound::Included(&start) => self.rank(start),
+            Bound::Excluded(&start) => self.rank(start) + self.contains_key(&start) as usize,
+            Bound::Unbounded => 0,
+        };
+        let upper = match range.end_bound() {
+            Bound::Included(&end) => self.rank(end) + self.contains_key(&end) as usize,
+            Bound::Excluded(&end) => self.rank(end),
+            Bound::Unbounded => self.len(),
+        };
+        upper.saturating_sub(lower)
+    }
+
+    /// Returns a lazy iterator around the keys present in the trie or in `other`, in ascending order
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut shard_1: Xfast<usize, &str> = Xfast::new(31);
+    ///     let mut shard_2: Xfast<usize, &str> = Xfast::new(31);
+    ///     shard_1.insert_key(1, "one");
+    ///     shard_1.insert_key(5, "five");
+    ///     shard_2.insert_key(5, "five");
+    ///     shard_2.insert_key(11, "eleven");
+    ///     assert_eq!(shard_1.union(&shard_2).collect::<Vec<usize>>(), vec![1, 5, 11]);
+    /// ```
+    pub fn union<'a, U, B: Storage<K, U>>(&'a self, other: &'a Xfast<K, U, S, B>) -> Union<'a, K, T, U, A, B> {
+        Union::new(self, other)
+    }
+
+    /// Returns a lazy iterator around the keys present both in the trie and in `other`, in ascending order.
+    ///
+    /// The successor queries of the tries are used to skip over the keys missing from the other trie.
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut shard_1: Xfast<usize, &str> = Xfast::new(31);
+    ///     let mut shard_2: Xfast<usize, &str> = Xfast::new(31);
+    ///     shard_1.insert_key(1, "one");
+    ///     shard_1.insert_key(5, "five");
+    ///     shard_2.insert_key(5, "five");
+    ///     shard_2.insert_key(11, "eleven");
+    ///     assert_eq!(shard_1.intersection(&shard_2).collect::<Vec<usize>>(), vec![5]);
+    /// ```
+    pub fn intersection<'a, U, B: Storage<K, U>>(&'a self, other: &'a Xfast<K, U, S, B>)
+        -> Intersection<'a, K, T, U, S, A, B> {
+        Intersection::new(self, other)
+    }
+
+    /// Returns a lazy iterator around the keys present in the trie but not in `other`, in ascending order
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut shard_1: Xfast<usize, &str> = Xfast::new(31);
+    ///     let mut shard_2: Xfast<usize, &str> = Xfast::new(31);
+    ///     shard_1.insert_key(1, "one");
+    ///     shard_1.insert_key(5, "five");
+    ///     shard_2.insert_key(5, "five");
+    ///     shard_2.insert_key(11, "eleven");
+    ///     assert_eq!(shard_1.difference(&shard_2).collect::<Vec<usize>>(), vec![1]);
+    /// ```
+    pub fn difference<'a, U, B: Storage<K, U>>(&'a self, other: &'a Xfast<K, U, S, B>)
+        -> Difference<'a, K, T, U, S, A, B> {
+        Difference::new(self, other)
+    }
+
+    /// Returns a lazy iterator around the keys present in exactly one of the trie and `other`, in ascending order
+    /// # Examples
+    /// ```
+    ///     use xfast::Xfast;
+    ///
+    ///     let mut shard_1: Xfast<usize, &str> = Xfast::new(31);
+    ///     let mut shard_2: Xfast<usize, &str> = Xfast::new(31);
+    ///     shard_1.insert_key(1, "one");
+    ///     shard_1.insert_key(5, "five");
+    ///     shard_2.insert_key(5, "five");
+    ///     shard_2.insert_key(11, "eleven");
+    ///     assert_eq!(shard_1.symmetric_difference(&shard_2).collect::<Vec<usize>>(), vec![1, 11]);
+    /// ```
+    pub fn symmetric_difference<'a, U, B: Storage<K, U>>(&'a self, other: &'a Xfast<K, U, S, B>)
+        -> SymmetricDifference<'a, K, T, U, A, B> {
+        SymmetricDifference::new(self, other)
+    }
+}
+
+impl<K: XfastKey, T: Clone, S: BuildHasher + Clone> Xfast<K, T, S, SharedArena<K, T>> {
+    /// Returns a read-only view of the trie as it is now, which later insertions and deletions don't change.
+    ///
+    /// Snapshots are taken from a trie storing its nodes in a `SharedArena`, see `new_in`. Taking a snapshot copies
+    /// one pointer per level of the trie. The levels and the nodes stay shared with the trie until the trie writes
+    /// to them: an insertion or deletion after the snapshot copies the shard of a few hundred prefixes it changes
+    /// in each level and the pages of 64 nodes it changes, along with the tables pointing to them.
+    /// # Examples
+    /// ```
+    ///     use xfast::{SharedArena, Xfast};
+    ///
+    ///     let mut test_trie = Xfast::new_in(31_usize, SharedArena::new());
+    ///     test_trie.insert_key(11, "eleven");
+    ///     test_trie.insert_key(1, "one");
+    ///     let snapshot = test_trie.snapshot();
+    ///     test_trie.delete_key(11);
+    ///     test_trie.insert_key(5, "five");
+    ///
+    ///     let keys: Vec<usize> = snapshot.iter().map(|(&key, _)| key).collect();
+    ///     assert_eq!(keys, vec![1, 11]);
+    ///     assert_eq!(snapshot.find_successor(2).map(|node| node.key()), Some(11));
+    ///     assert_eq!(test_trie.find_successor(2).map(|node| node.key()), Some(5));
+    /// ```
+    pub fn snapshot(&self) -> XfastSnapshot<K, T, S> {
+        XfastSnapshot::new(self)
+    }
+}
+
+/// Deep copy of the trie, the copy shares no node with the original.
+/// # Examples
+/// ```
+///     use xfast::Xfast;
+///
+///     let mut test_trie: Xfast<usize, String> = Xfast::new(31);
+///     test_trie.insert_key(11, String::from("eleven"));
+///     let mut copy = test_trie.clone();
+///     copy.insert_key(1, String::from("one"));
+///     assert_eq!(test_trie.len(), 1);
+///     assert_eq!(copy.len(), 2);
+/// ```
+impl<K: XfastKey, T: Clone, S: BuildHasher + Clone, A: Storage<K, T> + Default> Clone for Xfast<K, T, S, A> {
+    fn clone(&self) -> Self {
+        // the leaves come out of the original in order, so the node graph is rebuilt in a single pass
+        let pairs = self.iter().map(|(&key, node)| (key, node.value().clone()));
+        Xfast::from_sorted_iter_with_hasher_in(self.max_key, pairs, self.hasher().clone(), A::default())
+            .expect("keys of a trie are sorted, distinct and within its range")
+    }
+}
+
+/// Tries are equal when they store the same key and value pairs, whatever their ranges.
+/// # Examples
+/// ```
+///     use xfast::Xfast;
+///
+///     let mut small_trie: Xfast<usize, &str> = Xfast::new(31);
+///     let mut large_trie: Xfast<usize, &str> = Xfast::new(1023);
+///     small_trie.insert_key(11, "eleven");
+///     large_trie.insert_key(11, "eleven");
+///     assert_eq!(small_trie, large_trie);
+/// ```
+impl<K: XfastKey, T: PartialEq, S: BuildHasher, A: Storage<K, T>> PartialEq for Xfast<K, T, S, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.iter().zip(other.iter()).all(|((key, node), (other_key, other_node))| {
+                key == other_key && node.value() == other_node.value()
+            })
+    }
+}
+
+impl<K: XfastKey, T: Eq, S: BuildHasher, A: Storage<K, T>> Eq for Xfast<K, T, S, A> {}
+
+impl<K: XfastKey, T: Hash, S: BuildHasher, A: Storage<K, T>> Hash for Xfast<K, T, S, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // the length keeps the pairs of a trie apart from whatever is hashed after them
+        self.len().hash(state);
+        for (key, node) in self.iter() {
+            key.hash(state);
+            node.value().hash(state);
+        }
+    }
+}
+
+/// Formats the trie as a map of its key and value pairs in ascending order of the keys.
+/// # Examples
+/// ```
+///     use xfast::Xfast;
+///
+///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+///     test_trie.insert_key(5, "five");
+///     test_trie.insert_key(1, "one");
+///     assert_eq!(format!("{:?}", test_trie), r#"{1: "one", 5: "five"}"#);
+/// ```
+impl<K: XfastKey, T: fmt::Debug, S: BuildHasher, A: Storage<K, T>> fmt::Debug for Xfast<K, T, S, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter().map(|(key, node)| (key, node.value()))).finish()
+    }
+}
+
+/// Iterator around the Xfast key and value (TrieNode) pairs in ascending order of the keys
+pub struct XfastIter<'a, K, T, A = Arena<K, T>> {
+    arena: &'a A,
+    front: Option<NodeId>,
+    back: Option<NodeId>,
+    remaining: usize,
+    marker: PhantomData<&'a TrieNode<K, T>>,
+}
+
+impl<'a, K: XfastKey, T: 'a, A: Storage<K, T>> Iterator for XfastIter<'a, K, T, A> {
+    type Item = (&'a K, &'a TrieNode<K, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = &self.arena[self.front?];
+        self.remaining -= 1;
+        self.front = node.right;
+        Some((&node.key, node))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K: XfastKey, T: 'a, A: Storage<K, T>> DoubleEndedIterator for XfastIter<'a, K, T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = &self.arena[self.back?];
+        self.remaining -= 1;
+        self.back = node.left;
+        Some((&node.key, node))
+    }
+}
+
+impl<'a, K: XfastKey, T: 'a, A: Storage<K, T>> ExactSizeIterator for XfastIter<'a, K, T, A> {}
+
+impl<'a, K: XfastKey, T: 'a, A: Storage<K, T>> FusedIterator for XfastIter<'a, K, T, A> {}
+
+/// Mutable Iterator around the Xfast key and value (TrieNode) pairs in ascending order of the keys
+pub struct XfastIterMut<'a, K, T, S = RandomState, A = Arena<K, T>> {
+    leaves: LeavesMut<'a, K, T, A>,
+    // the keys are handed out from the leaf map as the nodes themselves are borrowed mutably
+    leaf_map: &'a Level<K, S>,
+    remaining: usize,
+}
+
+// key of a mutably borrowed leaf, borrowed from the leaf map instead of the leaf itself
+fn leaf_key<K: XfastKey, S: BuildHasher>(leaf_map: &Level<K, S>, key: K) -> &K {
+    let (key, _) = leaf_map.get_key_value(&key).expect("leaf missing from the leaf map");
+    key
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> Iterator for XfastIterMut<'a, K, T, S, A> {
+    type Item = (&'a K, &'a mut TrieNode<K, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.leaves.next_front()?;
+        self.remaining -= 1;
+        Some((leaf_key(self.leaf_map, node.key), node))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> DoubleEndedIterator for XfastIterMut<'a, K, T, S, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.leaves.next_back()?;
+        self.remaining -= 1;
+        Some((leaf_key(self.leaf_map, node.key), node))
+    }
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> ExactSizeIterator for XfastIterMut<'a, K, T, S, A> {}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> FusedIterator for XfastIterMut<'a, K, T, S, A> {}
+
+/// Iterator around the Xfast key and value (TrieNode) pairs within a range of keys
+pub struct XfastRange<'a, K, T, A = Arena<K, T>> {
+    arena: &'a A,
+    front: Option<NodeId>,
+    back: Option<NodeId>,
+    marker: PhantomData<&'a TrieNode<K, T>>,
+}
+
+impl<'a, K: XfastKey, T: 'a, A: Storage<K, T>> Iterator for XfastRange<'a, K, T, A> {
+    type Item = (&'a K, &'a TrieNode<K, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = &self.arena[self.front?];
+        // both ends met at the last node of the range
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        }
+        else {
+            self.front = node.right;
+        }
+        Some((&node.key, node))
+    }
+}
+
+impl<'a, K: XfastKey, T: 'a, A: Storage<K, T>> DoubleEndedIterator for XfastRange<'a, K, T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = &self.arena[self.back?];
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        }
+        else {
+            self.back = node.left;
+        }
+        Some((&node.key, node))
+    }
+}
+
+impl<'a, K: XfastKey, T: 'a, A: Storage<K, T>> FusedIterator for XfastRange<'a, K, T, A> {}
+
+/// Mutable Iterator around the Xfast key and value (TrieNode) pairs within a range of keys
+pub struct XfastRangeMut<'a, K, T, S = RandomState, A = Arena<K, T>> {
+    leaves: LeavesMut<'a, K, T, A>,
+    leaf_map: &'a Level<K, S>,
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> Iterator for XfastRangeMut<'a, K, T, S, A> {
+    type Item = (&'a K, &'a mut TrieNode<K, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.leaves.next_front()?;
+        Some((leaf_key(self.leaf_map, node.key), node))
+    }
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> DoubleEndedIterator for XfastRangeMut<'a, K, T, S, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.leaves.next_back()?;
+        Some((leaf_key(self.leaf_map, node.key), node))
+    }
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> FusedIterator for XfastRangeMut<'a, K, T, S, A> {}
+
+/// Owning Iterator around the Xfast key and value pairs in ascending order of the keys
+pub struct XfastIntoIter<K: XfastKey, T, S = RandomState, A = Arena<K, T>> {
+    // the trie still owns the nodes, the values are taken out of the leaves as they are yielded
+    trie: Xfast<K, T, S, A>,
+    front: Option<NodeId>,
+    back: Option<NodeId>,
+    remaining: usize,
+}
+
+impl<K: XfastKey, T, S, A: Storage<K, T>> Iterator for XfastIntoIter<K, T, S, A> {
+    type Item = (K, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = &mut self.trie.arena[self.front?];
+        self.remaining -= 1;
+        self.front = node.right;
+        node.value.take().map(|value| (node.key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K: XfastKey, T, S, A: Storage<K, T>> DoubleEndedIterator for XfastIntoIter<K, T, S, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = &mut self.trie.arena[self.back?];
+        self.remaining -= 1;
+        self.back = node.left;
+        node.value.take().map(|value| (node.key, value))
+    }
+}
+
+impl<K: XfastKey, T, S, A: Storage<K, T>> ExactSizeIterator for XfastIntoIter<K, T, S, A> {}
+
+impl<K: XfastKey, T, S, A: Storage<K, T>> FusedIterator for XfastIntoIter<K, T, S, A> {}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> IntoIterator for &'a Xfast<K, T, S, A> {
+    type Item = (&'a K, &'a TrieNode<K, T>);
+    type IntoIter = XfastIter<'a, K, T, A>;
+    fn into_iter(self) -> XfastIter<'a, K, T, A> {
+        self.iter()
+    }
+}
+
+impl<'a, K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> IntoIterator for &'a mut Xfast<K, T, S, A> {
+    type Item = (&'a K, &'a mut TrieNode<K, T>);
+    type IntoIter = XfastIterMut<'a, K, T, S, A>;
+    fn into_iter(self) -> XfastIterMut<'a, K, T, S, A> {
+        self.iter_mut()
+    }
+}
+
+/// Consumes the trie into its key and value pairs in ascending order of the keys.
+/// # Examples
+/// ```
+///     use xfast::Xfast;
+///
+///     let mut test_trie: Xfast<usize, String> = Xfast::new(31);
+///     test_trie.insert_key(11, String::from("eleven"));
+///     test_trie.insert_key(1, String::from("one"));
+///     let pairs: Vec<(usize, String)> = test_trie.into_iter().collect();
+///     assert_eq!(pairs, vec![(1, String::from("one")), (11, String::from("eleven"))]);
+/// ```
+impl<K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> IntoIterator for Xfast<K, T, S, A> {
+    type Item = (K, T);
+    type IntoIter = XfastIntoIter<K, T, S, A>;
+    fn into_iter(self) -> XfastIntoIter<K, T, S, A> {
+        XfastIntoIter {
+            front: self.first_leaf(),
+            back: self.last_leaf(),
+            remaining: self.len(),
+            trie: self,
+        }
+    }
+}
+
+/// Inserts every key and value pair of the iterator, replacing the values of keys already in the trie.
+///
+/// # Panics
+/// Panics if a key is larger than the range of the trie.
+/// # Examples
+/// ```
+///     use xfast::Xfast;
+///
+///     let mut test_trie: Xfast<usize, &str> = Xfast::new(31);
+///     test_trie.insert_key(11, "eleven");
+///     test_trie.extend(vec![(1, "one"), (11, "XI")]);
+///     assert_eq!(test_trie.len(), 2);
+///     assert_eq!(test_trie.get(&11), Some(&"XI"));
+/// ```
+impl<K: XfastKey, T, S: BuildHasher, A: Storage<K, T>> Extend<(K, T)> for Xfast<K, T, S, A> {
+    fn extend<I: IntoIterator<Item = (K, T)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert_key(key, value);
+        }
+    }
+}
+
+/// Collects key and value pairs in any order into a trie whose range is the largest key, the last value of a
+/// repeated key is kept.
+/// # Examples
+/// ```
+///     use xfast::Xfast;
+///
+///     let test_trie: Xfast<usize, &str> = vec![(11, "eleven"), (1, "one"), (11, "XI")].into_iter().collect();
+///     assert_eq!(test_trie.len(), 2);
+///     assert_eq!(test_trie.get(&11), Some(&"XI"));
+/// ```
+impl<K: XfastKey, T, S: BuildHasher + Clone + Default, A: Storage<K, T> + Default> FromIterator<(K, T)>
+    for Xfast<K, T, S, A> {
+    fn from_iter<I: IntoIterator<Item = (K, T)>>(iter: I) -> Self {
+        let mut pairs: Vec<(K, T)> = iter.into_iter().collect();
+        // the sort is stable, so the last value of a repeated key is the last one of its run
+        pairs.sort_by_key(|&(key, _)| key);
+        pairs.dedup_by(|later, earlier| {
+            if later.0 == earlier.0 {
+                std::mem::swap(later, earlier);
+                true
+            }
+            else {
+                false
+            }
+        });
+        let range = pairs.last().map_or(K::MIN, |&(key, _)| key);
+        Self::from_sorted_iter_with_hasher_in(range, pairs, S::default(), A::default()).expect("sorted pairs with distinct keys")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::collections::hash_map::RandomState;
+    use std::ops::Bound;
+
+    use super::{BuildIntegerHasher, SharedArena, Xfast, XfastError};
+
+    fn init()  -> Xfast<usize, String> {
+        let mut test_trie: Xfast<usize, String> = Xfast::new(31);
+        test_trie.insert_key(11, String::from("eleven"));
+        test_trie.insert_key(1, String::from("one"));
+        test_trie.insert_key(18, String::from("eighteen"));
+        test_trie.insert_key(5, String::from("five"));
+        test_trie
+    }
+
+    #[test]
+    fn successor() -> Result<(), String> {
+        let test_trie = init();
+        if let Some(successor) = test_trie.find_successor(7) {
+            if successor.key == 11 {
+                return Ok(())
+            }
+        }
+        Err(String::from("Successor of 7 is wrong"))
+    }
+
+    #[test]
+    fn none_successor() -> Result<(), String> {
+        let test_trie = init();
+        if test_trie.find_successor(19).is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Successor of 19 is wrong"))
+        }
+    }
+
+    #[test]
+    fn predecessor() -> Result<(), String> {
+        let test_trie = init();
+        if let Some(predecessor) = test_trie.find_predecessor(8) {
+            if predecessor.key == 5 {
+                return Ok(())
+            }
         }
         Err(String::from("Predecessor of 8 is wrong"))
     }
@@ -832,6 +2084,581 @@ mod test{
         Err(String::from("Successor of 18 is wrong"))
     }
 
+    #[test]
+    fn delete_returns_value() -> Result<(), String> {
+        let mut test_trie = init();
+        if test_trie.delete_key(5) == Some(String::from("five")) && test_trie.delete_key(5).is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Deleted value is wrong"))
+        }
+    }
+
+    #[test]
+    fn full_universe_u8() -> Result<(), String> {
+        let mut test_trie: Xfast<u8, u8> = Xfast::new(u8::MAX);
+        test_trie.insert_key(0, 0);
+        test_trie.insert_key(u8::MAX, u8::MAX);
+        test_trie.insert_key(128, 128);
+        let successor = test_trie.find_successor(129).map(|node| node.key);
+        let predecessor = test_trie.find_predecessor(127).map(|node| node.key);
+        if successor == Some(u8::MAX) && predecessor == Some(0) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Neighbours in a full u8 trie are wrong"))
+        }
+    }
+
+    #[test]
+    fn full_universe_u64() -> Result<(), String> {
+        let mut test_trie: Xfast<u64, &str> = Xfast::new(u64::MAX);
+        test_trie.insert_key(u64::MAX, "max");
+        test_trie.insert_key(1 << 40, "tera");
+        test_trie.delete_key(1 << 40);
+        if let Some(successor) = test_trie.find_successor(1) {
+            if successor.key == u64::MAX {
+                return Ok(());
+            }
+        }
+        Err(String::from("Successor of 1 is wrong"))
+    }
+
+    #[test]
+    fn u128_keys() -> Result<(), String> {
+        let mut test_trie: Xfast<u128, &str> = Xfast::new(u128::MAX);
+        let low = 0x2001_0db8_0000_0000_0000_0000_0000_0001;
+        let high = 0xfe80_0000_0000_0000_0000_0000_0000_0001;
+        test_trie.insert_key(low, "documentation");
+        test_trie.insert_key(high, "link local");
+        if let Some(predecessor) = test_trie.find_predecessor(high - 1) {
+            if predecessor.key == low {
+                return Ok(());
+            }
+        }
+        Err(String::from("Predecessor in a u128 trie is wrong"))
+    }
+
+    #[test]
+    fn insert_existing_key() -> Result<(), String> {
+        let mut test_trie = init();
+        let old_value = test_trie.insert_key(5, String::from("FIVE"));
+        let successor = test_trie.find_successor(2).map(|node| node.value().clone());
+        let predecessor = test_trie.find_predecessor(10).map(|node| node.value().clone());
+        if old_value == Some(String::from("five")) && test_trie.len() == 4
+            && successor == Some(String::from("FIVE")) && predecessor == Some(String::from("FIVE")) {
+            test_trie.delete_key(5);
+            if let Some(successor) = test_trie.find_successor(2) {
+                if successor.key == 11 {
+                    return Ok(());
+                }
+            }
+        }
+        Err(String::from("Inserting an existing key corrupted the trie"))
+    }
+
+    #[test]
+    fn insert_out_of_range() -> Result<(), String> {
+        let mut test_trie = init();
+        match test_trie.try_insert(32, String::from("thirty two")) {
+            Err(XfastError::KeyOutOfRange { key: 32, max: 31 }) if test_trie.len() == 4 => Ok(()),
+            _ => Err(String::from("Key larger than the range should be rejected")),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "key 32 is out of range")]
+    fn insert_key_out_of_range_panics() {
+        let mut test_trie = init();
+        test_trie.insert_key(32, String::from("thirty two"));
+    }
+
+    #[test]
+    fn sorted_iteration() -> Result<(), String> {
+        let mut test_trie = init();
+        test_trie.delete_key(11);
+        test_trie.insert_key(0, String::from("zero"));
+        test_trie.insert_key(31, String::from("thirty one"));
+        let keys: Vec<usize> = test_trie.iter().map(|(&key, _)| key).collect();
+        let reversed: Vec<usize> = test_trie.iter().rev().map(|(&key, _)| key).collect();
+        if keys == vec![0, 1, 5, 18, 31] && reversed == vec![31, 18, 5, 1, 0] && test_trie.iter().len() == 5 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Keys are not iterated in order"))
+        }
+    }
+
+    #[test]
+    fn iteration_from_both_ends() -> Result<(), String> {
+        let mut test_trie = init();
+        let mut iter = test_trie.iter_mut();
+        let first = iter.next().map(|(&key, _)| key);
+        let last = iter.next_back().map(|(&key, _)| key);
+        let remaining = iter.len();
+        let middle: Vec<usize> = iter.map(|(&key, _)| key).collect();
+        if first == Some(1) && last == Some(18) && remaining == 2 && middle == vec![5, 11] {
+            Ok(())
+        }
+        else {
+            Err(String::from("Iterators meeting in the middle yield wrong keys"))
+        }
+    }
+
+    #[test]
+    fn mutable_items_held_together() -> Result<(), String> {
+        // every item stays borrowed while the others are written, in an arena owned by the trie alone and across
+        // the pages of a shared arena, owned by the trie or shared with a snapshot
+        let pairs = (0..=255).step_by(3).map(|key| (key, u32::from(key)));
+        let mut test_trie: Xfast<u8, u32> = Xfast::from_sorted_iter(255, pairs.clone()).unwrap();
+        let mut items: Vec<_> = test_trie.iter_mut().collect();
+        for (_, node) in items.iter_mut() {
+            *node.value_mut() += 1;
+        }
+        let mut shared_trie = Xfast::new_in(255, SharedArena::new());
+        shared_trie.extend(pairs);
+        for (_, node) in shared_trie.iter_mut().collect::<Vec<_>>().iter_mut() {
+            *node.value_mut() += 1;
+        }
+        let snapshot = shared_trie.snapshot();
+        let mut items: Vec<_> = shared_trie.range_mut(10..100).collect();
+        for (_, node) in items.iter_mut().rev() {
+            *node.value_mut() += 1;
+        }
+        let written = |key: u8| u32::from(key) + if (10..100).contains(&key) { 2 } else { 1 };
+        if test_trie.iter().all(|(&key, node)| *node.value() == u32::from(key) + 1)
+            && shared_trie.iter().all(|(&key, node)| *node.value() == written(key))
+            && snapshot.iter().all(|(&key, node)| *node.value() == u32::from(key) + 1) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Writes through mutable items held together are lost"))
+        }
+    }
+
+    #[test]
+    fn range_bounds() -> Result<(), String> {
+        let test_trie = init();
+        let keys = |iter: super::XfastRange<usize, String>| iter.map(|(&key, _)| key).collect::<Vec<usize>>();
+        if keys(test_trie.range(5..18)) == vec![5, 11]
+            && keys(test_trie.range((Bound::Excluded(5), Bound::Included(18)))) == vec![11, 18]
+            && keys(test_trie.range(..)) == vec![1, 5, 11, 18]
+            && keys(test_trie.range(2..=usize::MAX)) == vec![5, 11, 18]
+            && keys(test_trie.range(6..11)).is_empty()
+            && keys(test_trie.range(19..)).is_empty()
+            && keys(test_trie.range((Bound::Excluded(1), Bound::Excluded(usize::MAX)))) == vec![5, 11, 18]
+            && keys(test_trie.range((Bound::Included(11), Bound::Excluded(11)))).is_empty() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Range iteration yields wrong keys"))
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end")]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn inverted_range_panics() {
+        let test_trie = init();
+        test_trie.range(5..3);
+    }
+
+    #[test]
+    #[should_panic(expected = "range start and end are equal and excluded")]
+    fn excluded_empty_range_panics() {
+        let mut test_trie = init();
+        test_trie.range_mut((Bound::Excluded(5), Bound::Excluded(5)));
+    }
+
+    #[test]
+    fn range_from_both_ends() -> Result<(), String> {
+        let mut test_trie = init();
+        let mut range = test_trie.range_mut(1..=11);
+        let last = range.next_back().map(|(&key, _)| key);
+        let first = range.next().map(|(&key, _)| key);
+        let middle = range.next_back().map(|(&key, _)| key);
+        if last == Some(11) && first == Some(1) && middle == Some(5) && range.next().is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Range iterators meeting in the middle yield wrong keys"))
+        }
+    }
+
+    #[test]
+    fn first_and_last() -> Result<(), String> {
+        let test_trie = init();
+        let first = test_trie.first().map(|node| node.key);
+        let last = test_trie.last().map(|node| node.key);
+        if first == Some(1) && last == Some(18) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Smallest or largest key is wrong"))
+        }
+    }
+
+    #[test]
+    fn ends_follow_inserts_and_deletes() -> Result<(), String> {
+        let mut test_trie: Xfast<usize, usize> = Xfast::from_sorted_iter(1023, vec![(100, 0), (200, 0)])
+            .map_err(|err| err.to_string())?;
+        let mut keys = std::collections::BTreeSet::from([100, 200]);
+        let mut key = 7;
+        for step in 0..500 {
+            key = (key * 37 + 11) % 1024;
+            if step % 3 == 2 {
+                let deleted = keys.iter().nth(key % (keys.len() + 1)).copied().unwrap_or(key);
+                test_trie.delete_key(deleted);
+                keys.remove(&deleted);
+            }
+            else {
+                test_trie.insert_key(key, step);
+                keys.insert(key);
+            }
+            let first = test_trie.first().map(|node| node.key);
+            let last = test_trie.last().map(|node| node.key);
+            if first != keys.first().copied() || last != keys.last().copied() {
+                return Err(String::from("Smallest or largest key is stale after a change"));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn pop_in_order() -> Result<(), String> {
+        let mut test_trie = init();
+        let mut keys = vec![];
+        while let Some((key, _)) = test_trie.pop_first() {
+            keys.push(key);
+            if let Some((key, _)) = test_trie.pop_last() {
+                keys.push(key);
+            }
+        }
+        if keys == vec![1, 18, 5, 11] && test_trie.is_empty() && test_trie.first().is_none() && test_trie.last().is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Keys are not popped in order"))
+        }
+    }
+
+    #[test]
+    fn map_api() -> Result<(), String> {
+        let mut test_trie = init();
+        if let Some(value) = test_trie.get_mut(&11) {
+            value.push('!');
+        }
+        let old_value = test_trie.insert(7, String::from("seven"));
+        if old_value.is_none() && test_trie.get(&11) == Some(&String::from("eleven!")) && test_trie.contains_key(&7)
+            && test_trie.remove(&7) == Some(String::from("seven")) && !test_trie.contains_key(&7) && test_trie.get(&7).is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Map API is inconsistent with the trie"))
+        }
+    }
+
+    #[test]
+    fn entry_upsert() -> Result<(), String> {
+        let mut test_trie = init();
+        test_trie.entry(5).and_modify(|value| value.push('!')).or_default();
+        test_trie.entry(7).and_modify(|value| value.push('!')).or_default();
+        let keys: Vec<usize> = test_trie.iter().map(|(&key, _)| key).collect();
+        let successor = test_trie.find_successor(6).map(|node| node.key);
+        if test_trie.get(&5) == Some(&String::from("five!")) && test_trie.get(&7) == Some(&String::new())
+            && keys == vec![1, 5, 7, 11, 18] && successor == Some(7) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Entry API is inconsistent with the trie"))
+        }
+    }
+
+    #[test]
+    fn strict_neighbours() -> Result<(), String> {
+        let test_trie = init();
+        let successor_5 = test_trie.find_strict_successor(5).map(|node| node.key);
+        let successor_6 = test_trie.find_strict_successor(6).map(|node| node.key);
+        let predecessor_5 = test_trie.find_strict_predecessor(5).map(|node| node.key);
+        let predecessor_4 = test_trie.find_strict_predecessor(4).map(|node| node.key);
+        if successor_5 == Some(11) && successor_6 == Some(11) && predecessor_5 == Some(1) && predecessor_4 == Some(1)
+            && test_trie.find_strict_successor(18).is_none() && test_trie.find_strict_predecessor(1).is_none()
+            && test_trie.find_strict_predecessor(usize::MAX).map(|node| node.key) == Some(18)
+            && test_trie.find_predecessor(32).map(|node| node.key) == Some(18)
+            && test_trie.find_strict_successor(32).is_none() && test_trie.find_successor(usize::MAX).is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Strict neighbours are wrong"))
+        }
+    }
+
+    #[test]
+    fn rank_and_select() -> Result<(), String> {
+        let mut test_trie = init();
+        test_trie.delete_key(11);
+        test_trie.insert_key(31, String::from("thirty one"));
+        let ranks: Vec<usize> = (0..32).map(|key| test_trie.rank(key)).collect();
+        let expected: Vec<usize> = (0..32).map(|key| test_trie.iter().filter(|(&other, _)| other < key).count()).collect();
+        let selected: Vec<usize> = (0..4).filter_map(|index| test_trie.select(index)).map(|node| node.key).collect();
+        if ranks == expected && selected == vec![1, 5, 18, 31] && test_trie.select(4).is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Rank or select is wrong"))
+        }
+    }
+
+    #[test]
+    fn count_range() -> Result<(), String> {
+        let test_trie = init();
+        if test_trie.count_range(..) == 4 && test_trie.count_range(5..18) == 2 && test_trie.count_range(5..=18) == 3
+            && test_trie.count_range((Bound::Excluded(5), Bound::Unbounded)) == 2 && test_trie.count_range(12..17) == 0 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Count of keys in range is wrong"))
+        }
+    }
+
+    #[test]
+    fn build_from_sorted() -> Result<(), String> {
+        let inserted = init();
+        let mut built = Xfast::from_sorted_iter(31, inserted.iter().map(|(&key, node)| (key, node.value().clone())))
+            .map_err(|err| err.to_string())?;
+        let same_neighbours = (0..32).all(|key| {
+            built.find_successor(key).map(|node| node.key) == inserted.find_successor(key).map(|node| node.key)
+                && built.find_predecessor(key).map(|node| node.key) == inserted.find_predecessor(key).map(|node| node.key)
+                && built.rank(key) == inserted.rank(key)
+        });
+        built.delete_key(18);
+        built.insert_key(31, String::from("thirty one"));
+        let keys: Vec<usize> = built.iter().map(|(&key, _)| key).collect();
+        if same_neighbours && keys == vec![1, 5, 11, 31] && built.select(3).map(|node| node.key) == Some(31) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Trie built from sorted pairs is wrong"))
+        }
+    }
+
+    #[test]
+    fn build_from_unsorted() -> Result<(), String> {
+        let unsorted = Xfast::from_sorted_iter(31_usize, vec![(5, "five"), (1, "one")]).err();
+        let duplicate = Xfast::from_sorted_iter(31_usize, vec![(5, "five"), (5, "V")]).err();
+        let out_of_range = Xfast::from_sorted_iter(31_usize, vec![(5, "five"), (32, "thirty two")]).err();
+        let collected: Xfast<usize, &str> = vec![(18, "eighteen"), (5, "five"), (18, "XVIII")].into_iter().collect();
+        if unsorted == Some(XfastError::UnsortedKey { key: 1, previous: 5 })
+            && duplicate == Some(XfastError::DuplicateKey { key: 5 })
+            && out_of_range == Some(XfastError::KeyOutOfRange { key: 32, max: 31 })
+            && collected.iter().map(|(&key, node)| (key, *node.value())).eq(vec![(5, "five"), (18, "XVIII")]) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Building from unsorted pairs is wrong"))
+        }
+    }
+
+    #[test]
+    fn owning_and_mutable_iteration() -> Result<(), String> {
+        let mut test_trie = init();
+        test_trie.extend(vec![(11, String::from("XI")), (31, String::from("thirty one"))]);
+        for (_, node) in &mut test_trie {
+            node.value_mut().push('!');
+        }
+        let mut pairs = test_trie.into_iter();
+        let last = pairs.next_back();
+        let rest: Vec<(usize, String)> = pairs.collect();
+        if last == Some((31, String::from("thirty one!")))
+            && rest == vec![(1, String::from("one!")), (5, String::from("five!")), (11, String::from("XI!")),
+                            (18, String::from("eighteen!"))] {
+            Ok(())
+        }
+        else {
+            Err(String::from("Owning or mutable iteration is wrong"))
+        }
+    }
+
+    #[test]
+    fn clone_is_independent() -> Result<(), String> {
+        let mut test_trie = init();
+        let mut copy = test_trie.clone();
+        let equal_after_clone = copy == test_trie;
+        copy.delete_key(5);
+        copy.insert_key(31, String::from("thirty one"));
+        if let Some(value) = copy.get_mut(&11) {
+            value.push('!');
+        }
+        test_trie.insert_key(7, String::from("seven"));
+        let keys: Vec<usize> = test_trie.iter().map(|(&key, _)| key).collect();
+        let copy_keys: Vec<usize> = copy.iter().map(|(&key, _)| key).collect();
+        if equal_after_clone && keys == vec![1, 5, 7, 11, 18] && copy_keys == vec![1, 11, 18, 31]
+            && test_trie.get(&11) == Some(&String::from("eleven")) && copy.find_predecessor(30).map(|node| node.key) == Some(18) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Clone shares state with the original trie"))
+        }
+    }
+
+    #[test]
+    fn snapshot_is_unchanged_by_writes() -> Result<(), String> {
+        let mut test_trie = Xfast::new_in(1023, SharedArena::new());
+        for key in (0..1024).step_by(3) {
+            test_trie.insert_key(key, key);
+        }
+        let snapshot = test_trie.snapshot();
+        let expected: Vec<(usize, usize)> = (0..1024).step_by(3).map(|key| (key, key)).collect();
+        for key in (0..1024).step_by(6) {
+            test_trie.delete_key(key);
+        }
+        for key in (1..1024).step_by(3) {
+            test_trie.insert_key(key, key * 2);
+        }
+        if let Some(value) = test_trie.get_mut(&3) {
+            *value = 0;
+        }
+        let later = test_trie.snapshot();
+        test_trie.insert_key(2, 2);
+
+        let entries: Vec<(usize, usize)> = snapshot.iter().map(|(&key, node)| (key, *node.value())).collect();
+        if entries == expected && snapshot.len() == expected.len() && snapshot.rank(10) == 4
+            && snapshot.find_predecessor(2).map(|node| node.key()) == Some(0)
+            && later.find_predecessor(2).map(|node| node.key()) == Some(1)
+            && test_trie.find_predecessor(2).map(|node| node.key()) == Some(2)
+            && snapshot.get(&3) == Some(&3) && later.get(&3) == Some(&0)
+            && later.len() + 1 == test_trie.len() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Snapshot changes after writes to the trie"))
+        }
+    }
+
+    #[test]
+    fn equality_hash_and_debug() -> Result<(), String> {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |trie: &Xfast<usize, String>| {
+            let mut hasher = DefaultHasher::new();
+            trie.hash(&mut hasher);
+            hasher.finish()
+        };
+        let test_trie = init();
+        let mut other: Xfast<usize, String> = Xfast::new(1023);
+        for (&key, node) in test_trie.iter().rev() {
+            other.insert_key(key, node.value().clone());
+        }
+        let equal = test_trie == other && hash(&test_trie) == hash(&other);
+        other.insert_key(5, String::from("V"));
+        let formatted = format!("{:?}", test_trie);
+        if equal && test_trie != other
+            && formatted == r#"{1: "one", 5: "five", 11: "eleven", 18: "eighteen"}"# {
+            Ok(())
+        }
+        else {
+            Err(String::from("Equality, hash or debug output is wrong"))
+        }
+    }
+
+    #[test]
+    fn integer_hasher() -> Result<(), String> {
+        let test_trie = init();
+        let mut fast_trie: Xfast<usize, String, BuildIntegerHasher> = Xfast::with_integer_hasher(31);
+        fast_trie.extend(test_trie.iter().map(|(&key, node)| (key, node.value().clone())));
+        let same_neighbours = (0..32).all(|key| {
+            fast_trie.find_successor(key).map(|node| node.key) == test_trie.find_successor(key).map(|node| node.key)
+                && fast_trie.find_predecessor(key).map(|node| node.key) == test_trie.find_predecessor(key).map(|node| node.key)
+        });
+        let copy = fast_trie.clone();
+        fast_trie.delete_key(11);
+        if same_neighbours && copy.len() == 4 && fast_trie.len() == 3 && fast_trie.find_successor(6).map(|node| node.key) == Some(18) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Trie with the integer hasher is wrong"))
+        }
+    }
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    // a trie is `Send` and `Sync` for any values which are, like the maps of std
+    fn send_with_values<T: Send>() {
+        assert_send::<Xfast<u64, T>>();
+        assert_send::<super::XfastIntoIter<u64, T>>();
+    }
+
+    fn sync_with_values<T: Sync>() {
+        assert_sync::<Xfast<u64, T>>();
+        assert_send::<super::XfastIter<'_, u64, T>>();
+    }
+
+    #[test]
+    fn send_and_sync() {
+        use std::cell::{Cell, RefCell};
+
+        send_with_values::<RefCell<u32>>();
+        sync_with_values::<u32>();
+        assert_send::<Xfast<u64, RefCell<u32>>>();
+        assert_send::<Xfast<u64, Cell<u32>, BuildIntegerHasher>>();
+        assert_send::<super::XfastIterMut<'static, u64, RefCell<u32>>>();
+        assert_send::<super::Entry<'static, u64, Cell<u32>>>();
+        assert_send::<Xfast<u64, String, RandomState, SharedArena<u64, String>>>();
+        assert_sync::<Xfast<u64, String, RandomState, SharedArena<u64, String>>>();
+        assert_send::<super::XfastSnapshot<u64, String>>();
+        assert_sync::<super::XfastSnapshot<u64, String>>();
+        assert_send::<Xfast<u64, String>>();
+        assert_sync::<Xfast<u64, String>>();
+        assert_send::<Xfast<u64, String, BuildIntegerHasher>>();
+        assert_sync::<Xfast<u64, String, BuildIntegerHasher>>();
+        assert_send::<super::TrieNode<u64, String>>();
+        assert_sync::<super::TrieNode<u64, String>>();
+        assert_send::<super::XfastIter<'static, u64, String>>();
+        assert_send::<super::XfastIterMut<'static, u64, String>>();
+        assert_send::<super::XfastRange<'static, u64, String>>();
+        assert_send::<super::XfastRangeMut<'static, u64, String>>();
+        assert_send::<super::XfastIntoIter<u64, String>>();
+        assert_send::<super::Entry<'static, u64, String>>();
+        assert_send::<super::Intersection<'static, u64, String, ()>>();
+        assert_send::<super::XfastSet<u64>>();
+        assert_sync::<super::XfastSet<u64>>();
+        assert_send::<super::Yfast<u64, String>>();
+        assert_sync::<super::Yfast<u64, String>>();
+    }
+
+    #[test]
+    fn shared_between_threads() -> Result<(), String> {
+        use std::sync::Arc;
+        use std::thread;
+
+        let mut test_trie = init();
+        // a trie moved into a worker thread and back
+        test_trie = thread::spawn(move || {
+            test_trie.insert_key(31, String::from("thirty one"));
+            test_trie
+        }).join().map_err(|_| String::from("Worker thread panicked"))?;
+        // and shared read only by many threads
+        let test_trie = Arc::new(test_trie);
+        let workers: Vec<_> = (0..4).map(|_| {
+            let test_trie = Arc::clone(&test_trie);
+            thread::spawn(move || test_trie.find_predecessor(30).map(|node| node.value().clone()))
+        }).collect();
+        for worker in workers {
+            if worker.join().map_err(|_| String::from("Reader thread panicked"))? != Some(String::from("eighteen")) {
+                return Err(String::from("Predecessor of 30 is wrong in a reader thread"));
+            }
+        }
+        if test_trie.len() == 5 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Trie moved across threads is wrong"))
+        }
+    }
+
     #[test]
     fn deleting_non_existent() -> Result<(), String> {
         let mut test_trie = init();
@@ -0,0 +1,496 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::iter::FusedIterator;
+use std::ops::RangeBounds;
+
+use crate::{BuildIntegerHasher, Difference, Intersection, SymmetricDifference, Union, Xfast, XfastError, XfastIter, XfastKey, XfastRange};
+
+/// A set of integers stored in a X-fast trie.
+///
+/// The set is an `Xfast` trie with values of unit type, so it answers membership, predecessor and successor
+/// queries the same way the trie does. The unit values take no room: the tag of `Option<()>` fits in the padding
+/// of a node, so a leaf of the set is exactly as large as a node which cannot store a value.
+/// # Examples
+/// ```
+///     use xfast::XfastSet;
+///
+///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+///     test_set.insert(11);
+///     test_set.insert(1);
+///     test_set.insert(5);
+///     assert_eq!(test_set.len(), 3);
+///     assert_eq!(test_set.predecessor(3), Some(1));
+///     assert_eq!(test_set.successor(6), Some(11));
+/// ```
+pub struct XfastSet<K = usize, S = RandomState> {
+    trie: Xfast<K, (), S>,
+}
+
+impl<K: XfastKey> XfastSet<K> {
+    /// Creates a new set to store a given `range` of integers
+    /// # Examples
+    /// ```
+    /// # #![allow(unused_mut)]
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut test_set: XfastSet<u32> = XfastSet::new(u32::MAX);
+    /// ```
+    pub fn new(range: K) -> Self {
+        XfastSet {
+            trie: Xfast::new(range),
+        }
+    }
+}
+
+impl<K: XfastKey> XfastSet<K, BuildIntegerHasher> {
+    /// Creates a new set to store a given `range` of integers, hashing the prefixes of the keys with
+    /// `IntegerHasher`, see `Xfast::with_integer_hasher`
+    pub fn with_integer_hasher(range: K) -> Self {
+        XfastSet {
+            trie: Xfast::with_integer_hasher(range),
+        }
+    }
+}
+
+impl<K: XfastKey, S: BuildHasher + Clone> XfastSet<K, S> {
+    /// Creates a new set to store a given `range` of integers, hashing the prefixes of the keys with `hasher`
+    pub fn with_hasher(range: K, hasher: S) -> Self {
+        XfastSet {
+            trie: Xfast::with_hasher(range, hasher),
+        }
+    }
+}
+
+impl<K: XfastKey, S: BuildHasher> XfastSet<K, S> {
+
+    /// Returns the count of keys stored in the set
+    pub fn len(&self) -> usize {
+        self.trie.len()
+    }
+
+    /// Returns true if the set stores no keys
+    pub fn is_empty(&self) -> bool {
+        self.trie.is_empty()
+    }
+
+    /// Adds `key` to the set, returning false if the set already had it.
+    ///
+    /// # Panics
+    /// Panics if `key` is larger than the range of the set.
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+    ///     assert!(test_set.insert(11));
+    ///     assert!(!test_set.insert(11));
+    /// ```
+    pub fn insert(&mut self, key: K) -> bool {
+        self.trie.insert_key(key, ()).is_none()
+    }
+
+    /// Adds `key` to the set, reporting keys larger than the range of the set as an error instead of panicking.
+    /// # Examples
+    /// ```
+    ///     use xfast::{XfastError, XfastSet};
+    ///
+    ///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+    ///     assert_eq!(test_set.try_insert(11), Ok(true));
+    ///     assert_eq!(test_set.try_insert(32), Err(XfastError::KeyOutOfRange { key: 32, max: 31 }));
+    /// ```
+    pub fn try_insert(&mut self, key: K) -> Result<bool, XfastError<K>> {
+        self.trie.try_insert(key, ()).map(|old_value| old_value.is_none())
+    }
+
+    /// Removes `key` from the set, returning false if the set didn't have it.
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+    ///     test_set.insert(11);
+    ///     assert!(test_set.remove(&11));
+    ///     assert!(!test_set.remove(&11));
+    /// ```
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.trie.remove(key).is_some()
+    }
+
+    /// Returns true if the set has `key`
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+    ///     test_set.insert(11);
+    ///     assert!(test_set.contains(&11));
+    ///     assert!(!test_set.contains(&12));
+    /// ```
+    pub fn contains(&self, key: &K) -> bool {
+        self.trie.contains_key(key)
+    }
+
+    /// Returns the largest key less than or equal to `key`. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+    ///     test_set.insert(11);
+    ///     assert_eq!(test_set.predecessor(11), Some(11));
+    ///     assert_eq!(test_set.predecessor(20), Some(11));
+    ///     assert_eq!(test_set.predecessor(10), None);
+    /// ```
+    pub fn predecessor(&self, key: K) -> Option<K> {
+        self.trie.find_predecessor(key).map(|node| node.key())
+    }
+
+    /// Returns the smallest key more than or equal to `key`. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+    ///     test_set.insert(11);
+    ///     assert_eq!(test_set.successor(11), Some(11));
+    ///     assert_eq!(test_set.successor(2), Some(11));
+    ///     assert_eq!(test_set.successor(12), None);
+    /// ```
+    pub fn successor(&self, key: K) -> Option<K> {
+        self.trie.find_successor(key).map(|node| node.key())
+    }
+
+    /// Returns the smallest key of the set
+    pub fn first(&self) -> Option<K> {
+        self.trie.first().map(|node| node.key())
+    }
+
+    /// Returns the largest key of the set
+    pub fn last(&self) -> Option<K> {
+        self.trie.last().map(|node| node.key())
+    }
+
+    /// Removes and returns the smallest key of the set
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.trie.pop_first().map(|(key, _)| key)
+    }
+
+    /// Removes and returns the largest key of the set
+    pub fn pop_last(&mut self) -> Option<K> {
+        self.trie.pop_last().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator around the keys of the set in ascending order
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+    ///     test_set.insert(11);
+    ///     test_set.insert(1);
+    ///     test_set.insert(19);
+    ///     assert_eq!(test_set.iter().collect::<Vec<usize>>(), vec![1, 11, 19]);
+    ///     assert_eq!(test_set.iter().rev().collect::<Vec<usize>>(), vec![19, 11, 1]);
+    /// ```
+    pub fn iter(&self) -> XfastSetIter<'_, K> {
+        XfastSetIter {
+            iter: self.trie.iter(),
+        }
+    }
+
+    /// Returns an iterator around the keys of the set within `range` in ascending order
+    ///
+    /// # Panics
+    /// Panics like `BTreeSet::range` if the start of `range` is larger than its end, or if both bounds are
+    /// excluded and equal.
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut test_set: XfastSet<usize> = XfastSet::new(31);
+    ///     test_set.insert(11);
+    ///     test_set.insert(1);
+    ///     test_set.insert(19);
+    ///     assert_eq!(test_set.range(2..=19).collect::<Vec<usize>>(), vec![11, 19]);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> XfastSetRange<'_, K> {
+        XfastSetRange {
+            range: self.trie.range(range),
+        }
+    }
+
+    /// Returns a lazy iterator around the keys present in the set or in `other`, in ascending order
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut shard_1: XfastSet<usize> = XfastSet::new(31);
+    ///     let mut shard_2: XfastSet<usize> = XfastSet::new(31);
+    ///     shard_1.insert(1);
+    ///     shard_1.insert(5);
+    ///     shard_2.insert(5);
+    ///     shard_2.insert(11);
+    ///     assert_eq!(shard_1.union(&shard_2).collect::<Vec<usize>>(), vec![1, 5, 11]);
+    /// ```
+    pub fn union<'a>(&'a self, other: &'a XfastSet<K, S>) -> Union<'a, K, (), ()> {
+        self.trie.union(&other.trie)
+    }
+
+    /// Returns a lazy iterator around the keys present both in the set and in `other`, in ascending order
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut shard_1: XfastSet<usize> = XfastSet::new(31);
+    ///     let mut shard_2: XfastSet<usize> = XfastSet::new(31);
+    ///     shard_1.insert(1);
+    ///     shard_1.insert(5);
+    ///     shard_2.insert(5);
+    ///     shard_2.insert(11);
+    ///     assert_eq!(shard_1.intersection(&shard_2).collect::<Vec<usize>>(), vec![5]);
+    /// ```
+    pub fn intersection<'a>(&'a self, other: &'a XfastSet<K, S>) -> Intersection<'a, K, (), (), S> {
+        self.trie.intersection(&other.trie)
+    }
+
+    /// Returns a lazy iterator around the keys present in the set but not in `other`, in ascending order
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut shard_1: XfastSet<usize> = XfastSet::new(31);
+    ///     let mut shard_2: XfastSet<usize> = XfastSet::new(31);
+    ///     shard_1.insert(1);
+    ///     shard_1.insert(5);
+    ///     shard_2.insert(5);
+    ///     shard_2.insert(11);
+    ///     assert_eq!(shard_1.difference(&shard_2).collect::<Vec<usize>>(), vec![1]);
+    /// ```
+    pub fn difference<'a>(&'a self, other: &'a XfastSet<K, S>) -> Difference<'a, K, (), (), S> {
+        self.trie.difference(&other.trie)
+    }
+
+    /// Returns a lazy iterator around the keys present in exactly one of the set and `other`, in ascending order
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut shard_1: XfastSet<usize> = XfastSet::new(31);
+    ///     let mut shard_2: XfastSet<usize> = XfastSet::new(31);
+    ///     shard_1.insert(1);
+    ///     shard_1.insert(5);
+    ///     shard_2.insert(5);
+    ///     shard_2.insert(11);
+    ///     assert_eq!(shard_1.symmetric_difference(&shard_2).collect::<Vec<usize>>(), vec![1, 11]);
+    /// ```
+    pub fn symmetric_difference<'a>(&'a self, other: &'a XfastSet<K, S>) -> SymmetricDifference<'a, K, (), ()> {
+        self.trie.symmetric_difference(&other.trie)
+    }
+
+    /// Returns true if the two sets have no keys in common
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut odd: XfastSet<usize> = XfastSet::new(31);
+    ///     let mut even: XfastSet<usize> = XfastSet::new(31);
+    ///     odd.insert(1);
+    ///     even.insert(2);
+    ///     assert!(odd.is_disjoint(&even));
+    ///     even.insert(1);
+    ///     assert!(!odd.is_disjoint(&even));
+    /// ```
+    pub fn is_disjoint(&self, other: &XfastSet<K, S>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    /// Returns true if every key of the set is in `other`
+    /// # Examples
+    /// ```
+    ///     use xfast::XfastSet;
+    ///
+    ///     let mut small: XfastSet<usize> = XfastSet::new(31);
+    ///     let mut large: XfastSet<usize> = XfastSet::new(31);
+    ///     small.insert(1);
+    ///     large.insert(1);
+    ///     large.insert(2);
+    ///     assert!(small.is_subset(&large));
+    ///     assert!(!large.is_subset(&small));
+    /// ```
+    pub fn is_subset(&self, other: &XfastSet<K, S>) -> bool {
+        self.len() <= other.len() && self.iter().all(|key| other.contains(&key))
+    }
+
+    /// Returns true if every key of `other` is in the set
+    pub fn is_superset(&self, other: &XfastSet<K, S>) -> bool {
+        other.is_subset(self)
+    }
+}
+
+impl<K: XfastKey, S: BuildHasher + Clone> Clone for XfastSet<K, S> {
+    fn clone(&self) -> Self {
+        XfastSet {
+            trie: self.trie.clone(),
+        }
+    }
+}
+
+impl<K: XfastKey, S: BuildHasher> PartialEq for XfastSet<K, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.trie == other.trie
+    }
+}
+
+impl<K: XfastKey, S: BuildHasher> Eq for XfastSet<K, S> {}
+
+impl<K: XfastKey, S: BuildHasher> Hash for XfastSet<K, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trie.hash(state);
+    }
+}
+
+/// Formats the set as its keys in ascending order, e.g. `{1, 5, 11}`
+impl<K: XfastKey, S: BuildHasher> fmt::Debug for XfastSet<K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K: XfastKey, S: BuildHasher> IntoIterator for &'a XfastSet<K, S> {
+    type Item = K;
+    type IntoIter = XfastSetIter<'a, K>;
+    fn into_iter(self) -> XfastSetIter<'a, K> {
+        self.iter()
+    }
+}
+
+/// Iterator around the keys of a `XfastSet` in ascending order
+pub struct XfastSetIter<'a, K> {
+    iter: XfastIter<'a, K, ()>,
+}
+
+impl<'a, K: XfastKey> Iterator for XfastSetIter<'a, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.iter.next().map(|(&key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: XfastKey> DoubleEndedIterator for XfastSetIter<'a, K> {
+    fn next_back(&mut self) -> Option<K> {
+        self.iter.next_back().map(|(&key, _)| key)
+    }
+}
+
+impl<'a, K: XfastKey> ExactSizeIterator for XfastSetIter<'a, K> {}
+
+impl<'a, K: XfastKey> FusedIterator for XfastSetIter<'a, K> {}
+
+/// Iterator around the keys of a `XfastSet` within a range of keys
+pub struct XfastSetRange<'a, K> {
+    range: XfastRange<'a, K, ()>,
+}
+
+impl<'a, K: XfastKey> Iterator for XfastSetRange<'a, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.range.next().map(|(&key, _)| key)
+    }
+}
+
+impl<'a, K: XfastKey> DoubleEndedIterator for XfastSetRange<'a, K> {
+    fn next_back(&mut self) -> Option<K> {
+        self.range.next_back().map(|(&key, _)| key)
+    }
+}
+
+impl<'a, K: XfastKey> FusedIterator for XfastSetRange<'a, K> {}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+    use std::mem::size_of;
+
+    use super::XfastSet;
+    use crate::TrieNode;
+
+    fn init() -> XfastSet<u16> {
+        let mut test_set: XfastSet<u16> = XfastSet::new(1000);
+        for &key in &[11, 1, 18, 5, 999] {
+            test_set.insert(key);
+        }
+        test_set
+    }
+
+    #[test]
+    fn membership() -> Result<(), String> {
+        let mut test_set = init();
+        if test_set.contains(&18) && test_set.remove(&18) && !test_set.contains(&18) && test_set.len() == 4 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Membership of 18 is wrong"))
+        }
+    }
+
+    #[test]
+    fn neighbours() -> Result<(), String> {
+        let test_set = init();
+        if test_set.successor(12) == Some(18) && test_set.predecessor(998) == Some(18)
+            && test_set.successor(1000).is_none() && test_set.predecessor(0).is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Neighbours in the set are wrong"))
+        }
+    }
+
+    #[test]
+    fn ordered_keys() -> Result<(), String> {
+        let mut test_set = init();
+        let keys: Vec<u16> = test_set.iter().collect();
+        let first = test_set.pop_first();
+        let last = test_set.pop_last();
+        if keys == vec![1, 5, 11, 18, 999] && first == Some(1) && last == Some(999) && test_set.first() == Some(5) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Keys of the set are not in order"))
+        }
+    }
+
+    #[test]
+    fn clone_and_debug() -> Result<(), String> {
+        let test_set = init();
+        let mut copy = test_set.clone();
+        let equal_after_clone = copy == test_set;
+        copy.remove(&999);
+        if equal_after_clone && copy != test_set && format!("{:?}", copy) == "{1, 5, 11, 18}" && test_set.contains(&999) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Cloned set is wrong"))
+        }
+    }
+
+    #[test]
+    fn unit_values_take_no_room() -> Result<(), String> {
+        // `Option<Infallible>` is a zero sized type, so these nodes have no room for a value at all
+        if size_of::<TrieNode<u16, ()>>() == size_of::<TrieNode<u16, Infallible>>()
+            && size_of::<TrieNode<u32, ()>>() == size_of::<TrieNode<u32, Infallible>>()
+            && size_of::<TrieNode<u64, ()>>() == size_of::<TrieNode<u64, Infallible>>()
+            && size_of::<Option<TrieNode<u64, ()>>>() == size_of::<Option<TrieNode<u64, Infallible>>>() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Leaves of the set are larger than nodes without values"))
+        }
+    }
+}
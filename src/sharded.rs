@@ -0,0 +1,330 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::iter::FusedIterator;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::vec;
+
+use crate::{Xfast, XfastError, XfastKey};
+
+/// A trie split into `2^shard_bits` shards on the most significant bits of the keys, each shard being an
+/// `Xfast` behind its own lock.
+///
+/// Writers to different shards don't contend with each other and readers of a shard only wait for a writer of
+/// the same shard. Every shard covers a contiguous block of keys, so a predecessor or successor query missing in
+/// the shard of its key falls through to the neighbouring shards, and visiting the shards in order visits the
+/// keys in order.
+///
+/// Each shard keeps the whole keys over the range of the trie, so the shards are as tall as a single `Xfast`.
+/// # Examples
+/// ```
+///     use std::sync::Arc;
+///     use std::thread;
+///     use xfast::ShardedXfast;
+///
+///     let test_trie: Arc<ShardedXfast<u32, u32>> = Arc::new(ShardedXfast::new(u32::MAX, 4));
+///     let writers: Vec<_> = (0..4_u32).map(|writer| {
+///         let test_trie = Arc::clone(&test_trie);
+///         thread::spawn(move || {
+///             for key in 0..100 {
+///                 test_trie.insert_key((writer << 30) + key, key);
+///             }
+///         })
+///     }).collect();
+///     for writer in writers {
+///         writer.join().unwrap();
+///     }
+///     assert_eq!(test_trie.len(), 400);
+///     assert_eq!(test_trie.find_successor(100), Some((1 << 30, 0)));
+/// ```
+pub struct ShardedXfast<K: XfastKey = usize, T = String, S = RandomState> {
+    shards: Vec<RwLock<Xfast<K, T, S>>>,
+    shard_bits: usize,
+    // count of the low bits of the keys below the shard bits
+    shift: usize,
+    max_key: K,
+}
+
+impl<K: XfastKey, T> ShardedXfast<K, T> {
+    /// Creates a new sharded trie to store a given `range` of integers in `2^shard_bits` shards.
+    ///
+    /// The shard count is capped to the count of keys in the universe of the trie.
+    /// # Examples
+    /// ```
+    ///     use xfast::ShardedXfast;
+    ///
+    ///     let test_trie: ShardedXfast<usize, &str> = ShardedXfast::new(31, 2);
+    ///     assert_eq!(test_trie.shard_count(), 4);
+    ///     let tiny_trie: ShardedXfast<usize, &str> = ShardedXfast::new(1, 2);
+    ///     assert_eq!(tiny_trie.shard_count(), 2);
+    /// ```
+    pub fn new(range: K, shard_bits: usize) -> Self {
+        Self::with_hasher(range, shard_bits, RandomState::new())
+    }
+}
+
+impl<K: XfastKey, T, S: BuildHasher + Clone> ShardedXfast<K, T, S> {
+    /// Creates a new sharded trie to store a given `range` of integers in `2^shard_bits` shards, hashing the
+    /// prefixes of the keys of every shard with `hasher`
+    pub fn with_hasher(range: K, shard_bits: usize, hasher: S) -> Self {
+        let nr_levels = Xfast::<K, T, S>::get_levels_count(range);
+        let shard_bits = shard_bits.min(nr_levels);
+        let shard_count = 1_usize.checked_shl(shard_bits as u32).expect("shard count fits in a usize");
+        ShardedXfast {
+            shards: (0..shard_count).map(|_| RwLock::new(Xfast::with_hasher(range, hasher.clone()))).collect(),
+            shard_bits,
+            shift: nr_levels - shard_bits,
+            max_key: range,
+        }
+    }
+}
+
+impl<K: XfastKey, T, S: BuildHasher> ShardedXfast<K, T, S> {
+    /// Returns the count of shards of the trie
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    // index of the shard storing `key`, read from the bits of the key right below the top of the universe
+    fn shard_index(&self, key: K) -> usize {
+        (0..self.shard_bits).fold(0, |index, bit| index | (key.bit(self.shift + bit) as usize) << bit)
+    }
+
+    // a panic of a writer may leave its shard half updated, so the poison is passed on to the other threads
+    fn read_shard(&self, index: usize) -> RwLockReadGuard<'_, Xfast<K, T, S>> {
+        self.shards[index].read().expect("a writer panicked while updating the shard")
+    }
+
+    fn write_shard(&self, index: usize) -> RwLockWriteGuard<'_, Xfast<K, T, S>> {
+        self.shards[index].write().expect("a writer panicked while updating the shard")
+    }
+
+    /// Returns the count of values stored in the trie, summed over the shards one shard at a time
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|index| self.read_shard(index).len()).sum()
+    }
+
+    /// Returns true if no shard stores a value
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|index| self.read_shard(index).is_empty())
+    }
+
+    /// Insert `key` and `value` into the shard of `key`.
+    ///
+    /// If the trie already has the `key`, its value is replaced and the old value is returned,
+    /// otherwise None is returned.
+    ///
+    /// # Panics
+    /// Panics if `key` is larger than the range of the trie.
+    pub fn insert_key(&self, key: K, value: T) -> Option<T> {
+        match self.try_insert(key, value) {
+            Ok(old_value) => old_value,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Insert `key` and `value` into the shard of `key`, reporting keys larger than the range of the trie as an
+    /// error instead of panicking.
+    pub fn try_insert(&self, key: K, value: T) -> Result<Option<T>, XfastError<K>> {
+        if key > self.max_key {
+            return Err(XfastError::KeyOutOfRange { key, max: self.max_key });
+        }
+        self.write_shard(self.shard_index(key)).try_insert(key, value)
+    }
+
+    /// Delete `key` from its shard. If the key doesn't exist it returns None else returns the value stored with
+    /// the deleted key.
+    pub fn delete_key(&self, key: K) -> Option<T> {
+        if key > self.max_key {
+            return None;
+        }
+        self.write_shard(self.shard_index(key)).delete_key(key)
+    }
+}
+
+impl<K: XfastKey, T: Clone, S: BuildHasher> ShardedXfast<K, T, S> {
+    /// Returns a clone of the value stored with `key`
+    pub fn find_key(&self, key: K) -> Option<T> {
+        if key > self.max_key {
+            return None;
+        }
+        self.read_shard(self.shard_index(key)).get(&key).cloned()
+    }
+
+    /// Returns the largest key less than or equal to `key` along with a clone of its value.
+    ///
+    /// The shard of `key` is searched first, then the shards of smaller keys from the nearest one, each under
+    /// its own lock.
+    /// # Examples
+    /// ```
+    ///     use xfast::ShardedXfast;
+    ///
+    ///     let test_trie: ShardedXfast<u8, &str> = ShardedXfast::new(u8::MAX, 2);
+    ///     test_trie.insert_key(3, "three");
+    ///     test_trie.insert_key(200, "two hundred");
+    ///     assert_eq!(test_trie.find_predecessor(150), Some((3, "three")));
+    ///     assert_eq!(test_trie.find_predecessor(2), None);
+    /// ```
+    pub fn find_predecessor(&self, key: K) -> Option<(K, T)> {
+        let key = key.min(self.max_key);
+        let index = self.shard_index(key);
+        if let Some(node) = self.read_shard(index).find_predecessor(key) {
+            return Some((node.key(), node.value().clone()));
+        }
+        (0..index).rev().find_map(|index| {
+            self.read_shard(index).last().map(|node| (node.key(), node.value().clone()))
+        })
+    }
+
+    /// Returns the smallest key more than or equal to `key` along with a clone of its value.
+    ///
+    /// The shard of `key` is searched first, then the shards of larger keys from the nearest one, each under
+    /// its own lock.
+    /// # Examples
+    /// ```
+    ///     use xfast::ShardedXfast;
+    ///
+    ///     let test_trie: ShardedXfast<u8, &str> = ShardedXfast::new(u8::MAX, 2);
+    ///     test_trie.insert_key(3, "three");
+    ///     test_trie.insert_key(200, "two hundred");
+    ///     assert_eq!(test_trie.find_successor(4), Some((200, "two hundred")));
+    ///     assert_eq!(test_trie.find_successor(201), None);
+    /// ```
+    pub fn find_successor(&self, key: K) -> Option<(K, T)> {
+        if key > self.max_key {
+            return None;
+        }
+        let index = self.shard_index(key);
+        if let Some(node) = self.read_shard(index).find_successor(key) {
+            return Some((node.key(), node.value().clone()));
+        }
+        (index + 1..self.shards.len()).find_map(|index| {
+            self.read_shard(index).first().map(|node| (node.key(), node.value().clone()))
+        })
+    }
+
+    /// Returns an iterator over clones of the keys and values of the trie in ascending order of the keys.
+    ///
+    /// The shards are read one after the other, each under its lock while its entries are cloned, so the
+    /// iterator doesn't hold any lock between two shards and sees the updates made meanwhile to the shards it
+    /// has not reached yet.
+    /// # Examples
+    /// ```
+    ///     use xfast::ShardedXfast;
+    ///
+    ///     let test_trie: ShardedXfast<u8, &str> = ShardedXfast::new(u8::MAX, 3);
+    ///     test_trie.insert_key(250, "two hundred and fifty");
+    ///     test_trie.insert_key(3, "three");
+    ///     test_trie.insert_key(100, "one hundred");
+    ///     let keys: Vec<u8> = test_trie.iter().map(|(key, _)| key).collect();
+    ///     assert_eq!(keys, vec![3, 100, 250]);
+    /// ```
+    pub fn iter(&self) -> ShardedXfastIter<'_, K, T, S> {
+        ShardedXfastIter {
+            trie: self,
+            next_shard: 0,
+            entries: Vec::new().into_iter(),
+        }
+    }
+}
+
+/// An iterator over the keys and values of a `ShardedXfast`, one shard after the other.
+///
+/// This is constructed from the `iter` method on `ShardedXfast`.
+pub struct ShardedXfastIter<'a, K: XfastKey, T, S = RandomState> {
+    trie: &'a ShardedXfast<K, T, S>,
+    next_shard: usize,
+    // entries of the current shard not yielded yet
+    entries: vec::IntoIter<(K, T)>,
+}
+
+impl<'a, K: XfastKey, T: Clone, S: BuildHasher> Iterator for ShardedXfastIter<'a, K, T, S> {
+    type Item = (K, T);
+
+    fn next(&mut self) -> Option<(K, T)> {
+        loop {
+            if let Some(entry) = self.entries.next() {
+                return Some(entry);
+            }
+            if self.next_shard == self.trie.shards.len() {
+                return None;
+            }
+            let shard = self.trie.read_shard(self.next_shard);
+            self.entries = shard.iter().map(|(&key, node)| (key, node.value().clone())).collect::<Vec<_>>().into_iter();
+            self.next_shard += 1;
+        }
+    }
+}
+
+impl<'a, K: XfastKey, T: Clone, S: BuildHasher> FusedIterator for ShardedXfastIter<'a, K, T, S> {}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::ShardedXfast;
+    use crate::XfastError;
+
+    #[test]
+    fn shards_follow_the_high_bits() -> Result<(), String> {
+        let test_trie: ShardedXfast<u8, u8> = ShardedXfast::new(u8::MAX, 2);
+        for key in [0, 63, 64, 127, 128, 255].iter() {
+            test_trie.insert_key(*key, *key);
+        }
+        let sizes: Vec<usize> = test_trie.shards.iter().map(|shard| shard.read().unwrap().len()).collect();
+        if sizes == vec![2, 2, 1, 1] && test_trie.shard_index(200) == 3 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Keys are stored in the wrong shards"))
+        }
+    }
+
+    #[test]
+    fn queries_fall_through_empty_shards() -> Result<(), String> {
+        let test_trie: ShardedXfast<u16, &str> = ShardedXfast::new(1023, 3);
+        test_trie.insert_key(5, "five");
+        test_trie.insert_key(1000, "one thousand");
+        let out_of_range = test_trie.try_insert(1024, "too large");
+        if test_trie.find_predecessor(999) == Some((5, "five"))
+            && test_trie.find_successor(6) == Some((1000, "one thousand"))
+            && test_trie.find_predecessor(u16::MAX) == Some((1000, "one thousand"))
+            && test_trie.find_successor(1001).is_none()
+            && test_trie.find_key(1000) == Some("one thousand")
+            && out_of_range == Err(XfastError::KeyOutOfRange { key: 1024, max: 1023 }) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Queries don't fall through to the neighbouring shards"))
+        }
+    }
+
+    #[test]
+    fn concurrent_writers_keep_global_order() -> Result<(), String> {
+        let test_trie: Arc<ShardedXfast<u32, u32>> = Arc::new(ShardedXfast::new(4095, 4));
+        let writers: Vec<_> = (0..4_u32).map(|writer| {
+            let test_trie = Arc::clone(&test_trie);
+            thread::spawn(move || {
+                // the writers interleave their keys over all the shards
+                for key in (writer..4096).step_by(4) {
+                    test_trie.insert_key(key, key * 2);
+                }
+                for key in (writer..4096).step_by(8) {
+                    test_trie.delete_key(key);
+                }
+            })
+        }).collect();
+        for writer in writers {
+            writer.join().map_err(|_| String::from("Writer thread panicked"))?;
+        }
+
+        let entries: Vec<(u32, u32)> = test_trie.iter().collect();
+        let expected: Vec<(u32, u32)> = (0..4096).filter(|key| key % 8 >= 4).map(|key| (key, key * 2)).collect();
+        if entries == expected && test_trie.len() == expected.len() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Sharded trie is out of order after concurrent writes"))
+        }
+    }
+}
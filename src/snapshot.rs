@@ -0,0 +1,67 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use crate::{SharedArena, TrieNode, Xfast, XfastIter, XfastKey};
+
+/// A read-only view of a trie as it was when the snapshot was taken.
+///
+/// The snapshot dereferences to `Xfast`, so every query and iteration method of the trie is available on it,
+/// while none of the methods changing the trie are.
+///
+/// The snapshot shares the levels and the nodes of the trie instead of copying them. While a snapshot shares
+/// them, a write of the trie copies the shard of a few hundred prefixes it changes in each level and the page of
+/// 64 nodes holding the node it changes, and writes to the copies from then on. So the snapshot never sees the
+/// later insertions and deletions, and the shards and pages left untouched stay shared until the snapshot is
+/// dropped.
+///
+/// This is constructed from the `snapshot` method on `Xfast`, for a trie storing its nodes in a `SharedArena`.
+pub struct XfastSnapshot<K = usize, T = String, S = RandomState> {
+    trie: Xfast<K, T, S, SharedArena<K, T>>,
+}
+
+impl<K: XfastKey, T: Clone, S: BuildHasher + Clone> XfastSnapshot<K, T, S> {
+    pub(crate) fn new(trie: &Xfast<K, T, S, SharedArena<K, T>>) -> Self {
+        XfastSnapshot {
+            trie: Xfast {
+                nr_levels: trie.nr_levels,
+                max_key: trie.max_key,
+                level_maps: trie.level_maps.clone(),
+                arena: trie.arena.share(),
+                ends: trie.ends,
+                marker: PhantomData,
+            },
+        }
+    }
+}
+
+impl<K, T, S> Deref for XfastSnapshot<K, T, S> {
+    type Target = Xfast<K, T, S, SharedArena<K, T>>;
+
+    fn deref(&self) -> &Xfast<K, T, S, SharedArena<K, T>> {
+        &self.trie
+    }
+}
+
+/// Cloning a snapshot shares the levels and the nodes of the snapshot again, without copying them.
+impl<K: XfastKey, T: Clone, S: BuildHasher + Clone> Clone for XfastSnapshot<K, T, S> {
+    fn clone(&self) -> Self {
+        XfastSnapshot::new(&self.trie)
+    }
+}
+
+impl<K: XfastKey, T: Clone + fmt::Debug, S: BuildHasher> fmt::Debug for XfastSnapshot<K, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.trie.fmt(f)
+    }
+}
+
+impl<'a, K: XfastKey, T: Clone, S: BuildHasher> IntoIterator for &'a XfastSnapshot<K, T, S> {
+    type Item = (&'a K, &'a TrieNode<K, T>);
+    type IntoIter = XfastIter<'a, K, T, SharedArena<K, T>>;
+    fn into_iter(self) -> XfastIter<'a, K, T, SharedArena<K, T>> {
+        self.trie.iter()
+    }
+}
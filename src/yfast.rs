@@ -0,0 +1,450 @@
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::iter::FusedIterator;
+
+use crate::{Xfast, XfastError, XfastIter, XfastKey};
+
+/// A [Y-fast Trie](https://en.wikipedia.org/wiki/Y-fast_trie) to store a bounded domain of integers in linear space.
+///
+/// The keys are grouped into balanced buckets of around log u keys each, u being the range of the trie. Only one
+/// representative key per bucket is stored in an `Xfast` trie, which brings the space down to O(n) while the
+/// queries still take O(log log u) time, amortised over the splits and merges of the buckets.
+///
+/// The representative of a bucket is a lower bound of its keys and the first bucket is always represented by
+/// the smallest key, so the bucket of a key is found with a predecessor query on the representatives.
+/// # Examples
+/// ```
+///     use xfast::Yfast;
+///
+///     let mut test_trie: Yfast<u64, &str> = Yfast::new(u64::MAX);
+///     test_trie.insert(11, "eleven");
+///     test_trie.insert(1, "one");
+///     test_trie.insert(5, "five");
+///     assert_eq!(test_trie.len(), 3);
+///     assert_eq!(test_trie.find_predecessor(3), Some((1, &"one")));
+///     assert_eq!(test_trie.find_successor(6), Some((11, &"eleven")));
+/// ```
+pub struct Yfast<K = usize, T = String> {
+    // buckets of the keys stored with their representatives
+    buckets: Xfast<K, BTreeMap<K, T>>,
+    // a bucket is split beyond twice this size and merged below half of it
+    bucket_size: usize,
+    len: usize,
+}
+
+impl<K: XfastKey, T> Yfast<K, T> {
+    /// Creates a new Yfast Trie to store a given `range` of integers
+    /// # Examples
+    /// ```
+    /// # #![allow(unused_mut)]
+    ///     use xfast::Yfast;
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    /// ```
+    pub fn new(range: K) -> Self {
+        let buckets = Xfast::new(range);
+        let bucket_size = buckets.nr_levels;
+        Yfast {
+            buckets,
+            bucket_size,
+            len: 0,
+        }
+    }
+
+    /// Returns the count of values stored in the trie
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the trie stores no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // representative of the bucket which holds or would hold `key`, keys beyond the range of the trie belong
+    // to the last bucket
+    fn bucket_key(&self, key: K) -> Option<K> {
+        self.buckets.find_predecessor(key.min(self.buckets.max_key)).map(|node| node.key())
+    }
+
+    /// Insert `key` and `value` into the trie, returning the value previously stored with `key`.
+    ///
+    /// # Panics
+    /// Panics if `key` is larger than the range of the trie.
+    /// # Examples
+    /// ```
+    ///     use xfast::Yfast;
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    ///     assert_eq!(test_trie.insert(11, "eleven"), None);
+    ///     assert_eq!(test_trie.insert(11, "XI"), Some("eleven"));
+    ///     assert_eq!(test_trie.len(), 1);
+    /// ```
+    pub fn insert(&mut self, key: K, value: T) -> Option<T> {
+        match self.try_insert(key, value) {
+            Ok(old_value) => old_value,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Insert `key` and `value` into the trie, reporting keys larger than the range of the trie as an error
+    /// instead of panicking.
+    /// # Examples
+    /// ```
+    ///     use xfast::{XfastError, Yfast};
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    ///     assert_eq!(test_trie.try_insert(11, "eleven"), Ok(None));
+    ///     assert_eq!(test_trie.try_insert(32, "thirty two"), Err(XfastError::KeyOutOfRange { key: 32, max: 31 }));
+    /// ```
+    pub fn try_insert(&mut self, key: K, value: T) -> Result<Option<T>, XfastError<K>> {
+        self.buckets.check_key(key)?;
+        let bucket_key = match self.bucket_key(key) {
+            Some(bucket_key) => bucket_key,
+            None => {
+                // the first bucket is represented by the smallest key
+                self.buckets.insert_key(K::MIN, BTreeMap::new());
+                K::MIN
+            }
+        };
+
+        let bucket = self.buckets.get_mut(&bucket_key).expect("bucket missing from the trie");
+        let old_value = bucket.insert(key, value);
+        if old_value.is_none() {
+            self.len += 1;
+            if bucket.len() > 2 * self.bucket_size {
+                self.split(bucket_key);
+            }
+        }
+        Ok(old_value)
+    }
+
+    // moves the upper half of an overfull bucket to a new bucket represented by its smallest key
+    fn split(&mut self, bucket_key: K) {
+        let bucket = self.buckets.get_mut(&bucket_key).expect("bucket missing from the trie");
+        let median = match bucket.keys().nth(bucket.len() / 2) {
+            Some(&median) => median,
+            None => return,
+        };
+        let upper_half = bucket.split_off(&median);
+        self.buckets.insert_key(median, upper_half);
+    }
+
+    // merges an underfull bucket with one of its neighbours, splitting the result again if it gets overfull
+    fn merge(&mut self, bucket_key: K) {
+        let next_key = self.buckets.find_strict_successor(bucket_key).map(|node| node.key());
+        let previous_key = self.buckets.find_strict_predecessor(bucket_key).map(|node| node.key());
+        let merged_key = match (next_key, previous_key) {
+            // the next bucket is merged into this one so that the first bucket keeps its representative
+            (Some(next_key), _) => {
+                let mut next_bucket = self.buckets.delete_key(next_key).expect("bucket missing from the trie");
+                let bucket = self.buckets.get_mut(&bucket_key).expect("bucket missing from the trie");
+                bucket.append(&mut next_bucket);
+                bucket_key
+            }
+            (None, Some(previous_key)) => {
+                let mut bucket = self.buckets.delete_key(bucket_key).expect("bucket missing from the trie");
+                let previous_bucket = self.buckets.get_mut(&previous_key).expect("bucket missing from the trie");
+                previous_bucket.append(&mut bucket);
+                previous_key
+            }
+            (None, None) => {
+                if self.buckets.get(&bucket_key).is_some_and(BTreeMap::is_empty) {
+                    self.buckets.delete_key(bucket_key);
+                }
+                return;
+            }
+        };
+        if self.buckets.get(&merged_key).map_or(0, BTreeMap::len) > 2 * self.bucket_size {
+            self.split(merged_key);
+        }
+    }
+
+    /// Removes `key` from the trie and returns its value. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Yfast;
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     assert_eq!(test_trie.remove(&11), Some("eleven"));
+    ///     assert_eq!(test_trie.remove(&11), None);
+    ///     assert!(test_trie.is_empty());
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<T> {
+        let bucket_key = self.bucket_key(*key)?;
+        let bucket = self.buckets.get_mut(&bucket_key)?;
+        let value = bucket.remove(key)?;
+        self.len -= 1;
+        // empty buckets are always merged away
+        if bucket.len() < (self.bucket_size / 2).max(1) {
+            self.merge(bucket_key);
+        }
+        Some(value)
+    }
+
+    /// Returns a reference to the value stored with `key`. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Yfast;
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     assert_eq!(test_trie.get(&11), Some(&"eleven"));
+    ///     assert_eq!(test_trie.get(&12), None);
+    /// ```
+    pub fn get(&self, key: &K) -> Option<&T> {
+        let bucket_key = self.bucket_key(*key)?;
+        self.buckets.get(&bucket_key)?.get(key)
+    }
+
+    /// Returns a mutable reference to the value stored with `key`. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Yfast;
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     if let Some(value) = test_trie.get_mut(&11) {
+    ///         *value = "XI";
+    ///     }
+    ///     assert_eq!(test_trie.get(&11), Some(&"XI"));
+    /// ```
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        let bucket_key = self.bucket_key(*key)?;
+        self.buckets.get_mut(&bucket_key)?.get_mut(key)
+    }
+
+    /// Returns true if the trie has a value stored with `key`
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the largest key less than or equal to `key` along with its value. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Yfast;
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     test_trie.insert(1, "one");
+    ///     assert_eq!(test_trie.find_predecessor(11), Some((11, &"eleven")));
+    ///     assert_eq!(test_trie.find_predecessor(10), Some((1, &"one")));
+    ///     assert_eq!(test_trie.find_predecessor(0), None);
+    /// ```
+    pub fn find_predecessor(&self, key: K) -> Option<(K, &T)> {
+        let bucket_key = self.bucket_key(key)?;
+        let bucket = self.buckets.get(&bucket_key)?;
+        if let Some((&key, value)) = bucket.range(..=key).next_back() {
+            return Some((key, value));
+        }
+        // the key is smaller than every key of its bucket, so the predecessor is the largest key of the previous bucket
+        let previous_bucket = self.buckets.find_strict_predecessor(bucket_key)?;
+        previous_bucket.value().iter().next_back().map(|(&key, value)| (key, value))
+    }
+
+    /// Returns the smallest key more than or equal to `key` along with its value. In case of no such key it returns None.
+    /// # Examples
+    /// ```
+    ///     use xfast::Yfast;
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     test_trie.insert(1, "one");
+    ///     assert_eq!(test_trie.find_successor(1), Some((1, &"one")));
+    ///     assert_eq!(test_trie.find_successor(2), Some((11, &"eleven")));
+    ///     assert_eq!(test_trie.find_successor(12), None);
+    /// ```
+    pub fn find_successor(&self, key: K) -> Option<(K, &T)> {
+        let bucket_key = self.bucket_key(key)?;
+        let bucket = self.buckets.get(&bucket_key)?;
+        if let Some((&key, value)) = bucket.range(key..).next() {
+            return Some((key, value));
+        }
+        // the key is larger than every key of its bucket, so the successor is the smallest key of the next bucket
+        let next_bucket = self.buckets.find_strict_successor(bucket_key)?;
+        next_bucket.value().iter().next().map(|(&key, value)| (key, value))
+    }
+
+    /// Returns the smallest key of the trie along with its value
+    pub fn first(&self) -> Option<(K, &T)> {
+        self.buckets.first()?.value().iter().next().map(|(&key, value)| (key, value))
+    }
+
+    /// Returns the largest key of the trie along with its value
+    pub fn last(&self) -> Option<(K, &T)> {
+        self.buckets.last()?.value().iter().next_back().map(|(&key, value)| (key, value))
+    }
+
+    /// Returns an iterator around all the key-value pairs stored in the trie in ascending order of the keys
+    /// # Examples
+    /// ```
+    ///     use xfast::Yfast;
+    ///
+    ///     let mut test_trie: Yfast<usize, &str> = Yfast::new(31);
+    ///     test_trie.insert(11, "eleven");
+    ///     test_trie.insert(1, "one");
+    ///     test_trie.insert(19, "nineteen");
+    ///     let keys: Vec<usize> = test_trie.iter().map(|(&key, _)| key).collect();
+    ///     assert_eq!(keys, vec![1, 11, 19]);
+    ///     let keys: Vec<usize> = test_trie.iter().rev().map(|(&key, _)| key).collect();
+    ///     assert_eq!(keys, vec![19, 11, 1]);
+    /// ```
+    pub fn iter(&self) -> YfastIter<'_, K, T> {
+        YfastIter {
+            buckets: self.buckets.iter(),
+            front: None,
+            back: None,
+            remaining: self.len,
+        }
+    }
+}
+
+impl<'a, K: XfastKey, T> IntoIterator for &'a Yfast<K, T> {
+    type Item = (&'a K, &'a T);
+    type IntoIter = YfastIter<'a, K, T>;
+    fn into_iter(self) -> YfastIter<'a, K, T> {
+        self.iter()
+    }
+}
+
+/// Iterator around the Yfast key and value pairs in ascending order of the keys
+pub struct YfastIter<'a, K, T> {
+    buckets: XfastIter<'a, K, BTreeMap<K, T>>,
+    // buckets being walked from the front and the back
+    front: Option<btree_map::Iter<'a, K, T>>,
+    back: Option<btree_map::Iter<'a, K, T>>,
+    remaining: usize,
+}
+
+impl<'a, K: XfastKey, T> Iterator for YfastIter<'a, K, T> {
+    type Item = (&'a K, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.front.as_mut().and_then(Iterator::next) {
+                self.remaining -= 1;
+                return Some(item);
+            }
+            match self.buckets.next() {
+                Some((_, bucket)) => self.front = Some(bucket.value().iter()),
+                // the back bucket is the only one left
+                None => {
+                    let item = self.back.as_mut().and_then(Iterator::next);
+                    if item.is_some() {
+                        self.remaining -= 1;
+                    }
+                    return item;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K: XfastKey, T> DoubleEndedIterator for YfastIter<'a, K, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.back.as_mut().and_then(DoubleEndedIterator::next_back) {
+                self.remaining -= 1;
+                return Some(item);
+            }
+            match self.buckets.next_back() {
+                Some((_, bucket)) => self.back = Some(bucket.value().iter()),
+                // the front bucket is the only one left
+                None => {
+                    let item = self.front.as_mut().and_then(DoubleEndedIterator::next_back);
+                    if item.is_some() {
+                        self.remaining -= 1;
+                    }
+                    return item;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K: XfastKey, T> ExactSizeIterator for YfastIter<'a, K, T> {}
+
+impl<'a, K: XfastKey, T> FusedIterator for YfastIter<'a, K, T> {}
+
+#[cfg(test)]
+mod test {
+    use super::Yfast;
+
+    fn init() -> Yfast<u32, u32> {
+        let mut test_trie: Yfast<u32, u32> = Yfast::new(4095);
+        for key in (0..4096).step_by(3) {
+            test_trie.insert(key, key * 10);
+        }
+        test_trie
+    }
+
+    #[test]
+    fn buckets_stay_balanced() -> Result<(), String> {
+        let mut test_trie = init();
+        for key in (0..4096).step_by(6) {
+            test_trie.remove(&key);
+        }
+        let bucket_size = test_trie.bucket_size;
+        let balanced = test_trie.buckets.iter().all(|(_, bucket)| {
+            !bucket.value().is_empty() && bucket.value().len() <= 2 * bucket_size
+        });
+        if balanced && test_trie.buckets.len() < test_trie.len() && test_trie.len() == 683 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Buckets of the trie are unbalanced"))
+        }
+    }
+
+    #[test]
+    fn neighbours() -> Result<(), String> {
+        let test_trie = init();
+        let keys: Vec<u32> = (0..4096).step_by(3).collect();
+        for query in 0..4096 {
+            let expected_successor = keys.iter().copied().find(|&key| key >= query);
+            let expected_predecessor = keys.iter().copied().rev().find(|&key| key <= query);
+            if test_trie.find_successor(query).map(|(key, _)| key) != expected_successor
+                || test_trie.find_predecessor(query).map(|(key, _)| key) != expected_predecessor {
+                return Err(format!("Neighbours of {} are wrong", query));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn remove_everything() -> Result<(), String> {
+        let mut test_trie = init();
+        for key in (0..4096).rev() {
+            if test_trie.contains_key(&key) && test_trie.remove(&key) != Some(key * 10) {
+                return Err(format!("Removing {} returned a wrong value", key));
+            }
+        }
+        if test_trie.is_empty() && test_trie.buckets.is_empty() && test_trie.first().is_none() {
+            Ok(())
+        }
+        else {
+            Err(String::from("Trie should be empty"))
+        }
+    }
+
+    #[test]
+    fn ordered_iteration() -> Result<(), String> {
+        let test_trie = init();
+        let keys: Vec<u32> = test_trie.iter().map(|(&key, _)| key).collect();
+        let mut iter = test_trie.iter();
+        let first = iter.next().map(|(&key, _)| key);
+        let last = iter.next_back().map(|(&key, _)| key);
+        if keys == (0..4096).step_by(3).collect::<Vec<u32>>() && first == Some(0) && last == Some(4095)
+            && iter.len() == keys.len() - 2 && iter.count() == keys.len() - 2 {
+            Ok(())
+        }
+        else {
+            Err(String::from("Keys are not iterated in order"))
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use xfast::Xfast;
+
+// Counts the live allocations made by the current thread so that allocations of the
+// test harness running on other threads don't affect the result.
+struct CountingAllocator;
+
+thread_local! {
+    static LIVE_ALLOCATIONS: Cell<isize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = LIVE_ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = LIVE_ALLOCATIONS.try_with(|count| count.set(count.get() - 1));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn live_allocations() -> isize {
+    LIVE_ALLOCATIONS.with(|count| count.get())
+}
+
+#[test]
+fn allocations_are_released() {
+    let before = live_allocations();
+    {
+        let mut test_trie: Xfast<usize, String> = Xfast::new(1023);
+        for key in (0..1024).step_by(7) {
+            test_trie.insert_key(key, key.to_string());
+        }
+        for key in (0..1024).step_by(21) {
+            test_trie.delete_key(key);
+        }
+        assert!(!test_trie.is_empty());
+    }
+    assert_eq!(live_allocations(), before);
+
+    {
+        // deleting every key must also free the internal nodes on the way
+        let mut test_trie: Xfast<usize, String> = Xfast::new(255);
+        for key in 0..256 {
+            test_trie.insert_key(key, key.to_string());
+        }
+        for key in 0..256 {
+            test_trie.delete_key(key);
+        }
+        assert!(test_trie.is_empty());
+        test_trie.insert_key(42, String::from("forty two"));
+    }
+    assert_eq!(live_allocations(), before);
+
+    {
+        // values left in a partly consumed owning iterator are dropped along with the trie
+        let test_trie: Xfast<usize, String> = (0..64).map(|key| (key, key.to_string())).collect();
+        let mut pairs = test_trie.into_iter();
+        assert_eq!(pairs.next(), Some((0, String::from("0"))));
+        assert_eq!(pairs.next_back(), Some((63, String::from("63"))));
+    }
+    assert_eq!(live_allocations(), before);
+}